@@ -0,0 +1,30 @@
+use chacha20poly1305::aead::{consts::U32, AeadInPlace, KeyInit, KeySizeUser};
+pub use chacha20poly1305::{XChaCha12Poly1305, XChaCha20Poly1305, XChaCha8Poly1305};
+
+#[cfg(feature = "aes-gcm")]
+pub use aes_gcm::Aes256Gcm;
+
+// An AEAD that `EncryptedMem` can seal into. Every backend takes a 32 byte key so the `SealingKeyVault` output works
+// with any of them, the nonce and tag lengths come from the cipher itself
+pub trait Cipher: AeadInPlace + KeyInit + KeySizeUser<KeySize = U32> {
+    // Recorded in the wire format so a reader can tell which cipher sealed a blob
+    const ALGORITHM: u8;
+}
+
+impl Cipher for XChaCha8Poly1305 {
+    const ALGORITHM: u8 = 1;
+}
+
+impl Cipher for XChaCha12Poly1305 {
+    const ALGORITHM: u8 = 2;
+}
+
+impl Cipher for XChaCha20Poly1305 {
+    const ALGORITHM: u8 = 3;
+}
+
+// Uses a random 12 byte nonce, so a single key should seal well under 2^32 messages
+#[cfg(feature = "aes-gcm")]
+impl Cipher for Aes256Gcm {
+    const ALGORITHM: u8 = 4;
+}
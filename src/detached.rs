@@ -0,0 +1,121 @@
+#[cfg(feature = "getrandom")]
+use crate::stack::seal_inline;
+use crate::{
+    stack::open_inline, Cipher, SecureMemError, XChaCha8Poly1305, ZeroizeArray,
+    KEY_COMMITMENT_LENGTH,
+};
+use chacha20poly1305::{
+    aead::{Nonce, Tag},
+    Key,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// For formats that keep the tag apart from the ciphertext. The ciphertext is exactly `N` bytes and the tag is
+// handed back to the caller instead of being stored, so it cannot be mistaken for an `EncryptedMem` layout.
+pub struct DetachedCiphertext<const N: usize, C: Cipher = XChaCha8Poly1305> {
+    ciphertext: ZeroizeArray<N>,
+    nonce: Nonce<C>,
+    key_commitment: Option<[u8; KEY_COMMITMENT_LENGTH]>,
+}
+
+impl<const N: usize, C: Cipher> DetachedCiphertext<N, C> {
+    // Reassembles a ciphertext whose parts were stored or sent separately
+    pub fn from_parts(
+        ciphertext: ZeroizeArray<N>,
+        nonce: Nonce<C>,
+        key_commitment: Option<[u8; KEY_COMMITMENT_LENGTH]>,
+    ) -> Self {
+        Self {
+            ciphertext,
+            nonce,
+            key_commitment,
+        }
+    }
+
+    pub fn ciphertext(&self) -> &ZeroizeArray<N> {
+        &self.ciphertext
+    }
+
+    pub fn nonce(&self) -> &Nonce<C> {
+        &self.nonce
+    }
+
+    pub fn key_commitment(&self) -> Option<&[u8; KEY_COMMITMENT_LENGTH]> {
+        self.key_commitment.as_ref()
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt(
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+    ) -> Result<(Self, Tag<C>), SecureMemError> {
+        Self::encrypt_with_aad(plaintext, key, b"")
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_aad(
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<(Self, Tag<C>), SecureMemError> {
+        let (ciphertext, tag, nonce, key_commitment) = seal_inline::<N, C>(plaintext, key, aad)?;
+
+        Ok((
+            Self {
+                ciphertext,
+                nonce,
+                key_commitment,
+            },
+            tag,
+        ))
+    }
+
+    pub fn decrypt(&self, key: &Key, tag: &Tag<C>) -> Result<ZeroizeArray<N>, SecureMemError> {
+        self.decrypt_with_aad(key, tag, b"")
+    }
+
+    pub fn decrypt_with_aad(
+        &self,
+        key: &Key,
+        tag: &Tag<C>,
+        aad: &[u8],
+    ) -> Result<ZeroizeArray<N>, SecureMemError> {
+        let mut plaintext = ZeroizeArray::<N>::zeroed();
+        open_inline::<N, C>(
+            &self.ciphertext,
+            tag,
+            &self.nonce,
+            self.key_commitment.as_ref(),
+            key,
+            aad,
+            &mut plaintext,
+        )?;
+
+        Ok(plaintext)
+    }
+}
+
+impl<const N: usize, C: Cipher> Zeroize for DetachedCiphertext<N, C> {
+    fn zeroize(&mut self) {
+        self.ciphertext.zeroize();
+        self.nonce.as_mut_slice().zeroize();
+        if let Some(key_commitment) = self.key_commitment.as_mut() {
+            key_commitment.zeroize();
+        }
+        self.key_commitment = None;
+    }
+}
+
+impl<const N: usize, C: Cipher> Drop for DetachedCiphertext<N, C> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<const N: usize, C: Cipher> ZeroizeOnDrop for DetachedCiphertext<N, C> {}
+
+impl<const N: usize, C: Cipher> core::fmt::Debug for DetachedCiphertext<N, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "DetachedCiphertext([REDACTED; {N}])")
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{store::wipe_bytes_mut, Cipher, EncryptedMem, SecureMemError, ZeroizeString};
+use chacha20poly1305::aead::bytes::BytesMut;
+#[cfg(feature = "hex")]
+use {
+    crate::{ZeroizeArray, ZeroizeBytes},
+    alloc::string::String,
+    zeroize::Zeroize,
+};
+
+// `UrlSafe` is the unpadded `-_` alphabet from RFC 4648 section 5, `Standard` is the padded `+/` one
+#[cfg(feature = "base64")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Base64Alphabet {
+    #[default]
+    UrlSafe,
+    Standard,
+}
+
+// Text encodings of the `to_bytes` wire format. Decoders are constant time and strict: they reject bad padding,
+// odd length hex and embedded whitespace, and every intermediate buffer is wiped before returning.
+impl<const N: usize, C: Cipher> EncryptedMem<N, C> {
+    // URL safe and unpadded, so the output can go into tokens and query parameters as is
+    #[cfg(feature = "base64")]
+    pub fn to_base64(&self) -> ZeroizeString {
+        self.to_base64_with(Base64Alphabet::UrlSafe)
+    }
+
+    #[cfg(feature = "base64")]
+    pub fn to_base64_with(&self, alphabet: Base64Alphabet) -> ZeroizeString {
+        use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+
+        let bytes = self.to_bytes();
+        ZeroizeString::new(match alphabet {
+            Base64Alphabet::UrlSafe => Base64UrlUnpadded::encode_string(&bytes.expose()[..]),
+            Base64Alphabet::Standard => Base64::encode_string(&bytes.expose()[..]),
+        })
+    }
+
+    #[cfg(feature = "base64")]
+    pub fn from_base64(encoded: &str) -> Result<Self, SecureMemError> {
+        Self::from_base64_with(encoded, Base64Alphabet::UrlSafe)
+    }
+
+    #[cfg(feature = "base64")]
+    pub fn from_base64_with(
+        encoded: &str,
+        alphabet: Base64Alphabet,
+    ) -> Result<Self, SecureMemError> {
+        use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+
+        decode_with(encoded.len() / 4 * 3 + 3, |buffer| {
+            match alphabet {
+                Base64Alphabet::UrlSafe => Base64UrlUnpadded::decode(encoded, buffer),
+                Base64Alphabet::Standard => Base64::decode(encoded, buffer),
+            }
+            .map_err(|_| SecureMemError::InvalidEncoding)
+        })
+    }
+
+    #[cfg(feature = "hex")]
+    pub fn to_hex(&self) -> ZeroizeString {
+        ZeroizeString::new(base16ct::lower::encode_string(
+            &self.to_bytes().expose()[..],
+        ))
+    }
+
+    #[cfg(feature = "hex")]
+    pub fn from_hex(encoded: &str) -> Result<Self, SecureMemError> {
+        decode_with(encoded.len() / 2, |buffer| {
+            base16ct::mixed::decode(encoded, buffer).map_err(|_| SecureMemError::InvalidEncoding)
+        })
+    }
+}
+
+#[cfg(feature = "hex")]
+impl<const N: usize> ZeroizeArray<N> {
+    pub fn to_hex(&self) -> ZeroizeString {
+        ZeroizeString::new(base16ct::lower::encode_string(self.expose_borrowed()))
+    }
+
+    // Decodes straight into the array, which is wiped again if the input is not exactly `2 * N` hex digits
+    pub fn from_hex(encoded: &str) -> Result<Self, SecureMemError> {
+        // Well formed hex of the wrong length is `InvalidLength` whether it is too short or too long, base16ct would
+        // report the too long case as a bad encoding
+        if encoded.len().is_multiple_of(2) && encoded.len() != 2 * N {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let mut array = ZeroizeArray::<N>::zeroed();
+        let decoded =
+            array.expose_with_mut(|buffer| match base16ct::mixed::decode(encoded, buffer) {
+                Ok(decoded) if decoded.len() == N => Ok(()),
+                Ok(_) => Err(SecureMemError::InvalidLength),
+                Err(_) => Err(SecureMemError::InvalidEncoding),
+            });
+
+        match decoded {
+            Ok(()) => Ok(array),
+            Err(error) => {
+                array.zeroize();
+
+                Err(error)
+            }
+        }
+    }
+
+    // For keys pasted into prompts or read from the environment, the string is wiped whether or not it decodes
+    pub fn from_hex_string(mut encoded: String) -> Result<Self, SecureMemError> {
+        let outcome = Self::from_hex(&encoded);
+        encoded.zeroize();
+
+        outcome
+    }
+}
+
+#[cfg(feature = "hex")]
+impl ZeroizeBytes {
+    pub fn to_hex(&self) -> ZeroizeString {
+        ZeroizeString::new(base16ct::lower::encode_string(&self.0[..]))
+    }
+
+    pub fn from_hex(encoded: &str) -> Result<Self, SecureMemError> {
+        let mut buffer = BytesMut::zeroed(encoded.len() / 2);
+        match base16ct::mixed::decode(encoded, &mut buffer[..]) {
+            Ok(_) => Ok(ZeroizeBytes(buffer)),
+            Err(_) => {
+                wipe_bytes_mut(&mut buffer);
+
+                Err(SecureMemError::InvalidEncoding)
+            }
+        }
+    }
+
+    pub fn from_hex_string(mut encoded: String) -> Result<Self, SecureMemError> {
+        let outcome = Self::from_hex(&encoded);
+        encoded.zeroize();
+
+        outcome
+    }
+}
+
+fn decode_with<const N: usize, C: Cipher>(
+    capacity: usize,
+    decode: impl FnOnce(&mut [u8]) -> Result<&[u8], SecureMemError>,
+) -> Result<EncryptedMem<N, C>, SecureMemError> {
+    let mut buffer = BytesMut::zeroed(capacity);
+    let outcome = decode(&mut buffer[..]).and_then(EncryptedMem::from_bytes);
+    wipe_bytes_mut(&mut buffer);
+
+    outcome
+}
@@ -0,0 +1,78 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecureMemError {
+    Encryption,
+    AuthenticationFailed,
+    InvalidLength,
+    InvalidHeader,
+    IndexOutOfBounds,
+    WrongKey,
+    Corrupted,
+    MemoryLock,
+    KeyDerivation,
+    AlgorithmMismatch,
+    InvalidNonce,
+    InvalidString,
+    RandomnessUnavailable,
+    CounterExhausted,
+    InvalidEncoding,
+    InvalidShares,
+    InvalidAlphabet,
+    Io,
+    InvalidPublicKey,
+    UnknownWord,
+    InvalidChecksum,
+    // Lengths are public, so unlike the other variants this one reports what it saw
+    LengthMismatch { expected: usize, actual: usize },
+}
+
+impl core::fmt::Display for SecureMemError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let message = match self {
+            SecureMemError::Encryption => "encryption failed",
+            SecureMemError::AuthenticationFailed => {
+                "authentication failed: wrong key or tampered ciphertext"
+            }
+            SecureMemError::InvalidLength => "invalid length",
+            SecureMemError::InvalidHeader => "invalid magic or version header",
+            SecureMemError::IndexOutOfBounds => "index out of bounds",
+            SecureMemError::WrongKey => "key does not match the key commitment",
+            SecureMemError::Corrupted => "authentication failed: ciphertext is corrupted",
+            SecureMemError::MemoryLock => {
+                "failed to lock memory, the locked memory limit may be exceeded"
+            }
+            SecureMemError::KeyDerivation => "key derivation failed: invalid parameters or salt",
+            SecureMemError::AlgorithmMismatch => "ciphertext was sealed with a different algorithm",
+            SecureMemError::InvalidNonce => "invalid nonce: an all zero nonce was not allowed",
+            SecureMemError::InvalidString => "decrypted bytes are not a valid unicode string",
+            SecureMemError::RandomnessUnavailable => "the random number generator failed",
+            SecureMemError::InvalidEncoding => {
+                "invalid text encoding: bad padding, length or characters"
+            }
+            SecureMemError::CounterExhausted => {
+                "nonce counter exhausted: encrypting more would reuse a nonce"
+            }
+            SecureMemError::InvalidShares => {
+                "invalid secret shares: bad threshold, too few, duplicate or malformed shares"
+            }
+            SecureMemError::InvalidAlphabet => {
+                "invalid token alphabet: empty, not ascii or has repeated characters"
+            }
+            SecureMemError::Io => "reading or writing the secret failed",
+            SecureMemError::InvalidPublicKey => {
+                "invalid public key: a low order point gives no shared secret"
+            }
+            SecureMemError::UnknownWord => "mnemonic has a word that is not in the wordlist",
+            SecureMemError::InvalidChecksum => "mnemonic checksum does not match, a word is wrong",
+            SecureMemError::LengthMismatch { expected, actual } => {
+                return write!(
+                    f,
+                    "length mismatch: expected {expected} bytes, got {actual}"
+                );
+            }
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl core::error::Error for SecureMemError {}
@@ -0,0 +1,78 @@
+use crate::{store::try_fill_random, Cipher, EncryptedMem, SecureMemError};
+use rand_core::OsRng;
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroize;
+
+impl<const N: usize, C: Cipher> EncryptedMem<N, C> {
+    // Writes the `to_bytes` wire format to a new file next to `path` and renames it over `path` once it is synced, so
+    // a crash or a full disk midway leaves any previous file intact. On Unix the file is created with mode 0600.
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), SecureMemError> {
+        let path = path.as_ref();
+        let temporary = temporary_path(path)?;
+
+        let outcome = write_new(&temporary, &self.to_bytes().expose()[..])
+            .and_then(|_| std::fs::rename(&temporary, path).map_err(|_| SecureMemError::Io));
+        if outcome.is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
+        outcome?;
+
+        // The rename itself only survives a crash once the directory entry is synced
+        #[cfg(unix)]
+        std::fs::File::open(parent(path))
+            .and_then(|directory| directory.sync_all())
+            .map_err(|_| SecureMemError::Io)?;
+
+        Ok(())
+    }
+
+    // Goes through `from_bytes`, so the magic, version, algorithm and length are all checked
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, SecureMemError> {
+        let mut bytes = std::fs::read(path).map_err(|_| SecureMemError::Io)?;
+        let outcome = Self::from_bytes(&bytes);
+        bytes.zeroize();
+
+        outcome
+    }
+}
+
+fn write_new(path: &Path, bytes: &[u8]) -> Result<(), SecureMemError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).map_err(|_| SecureMemError::Io)?;
+    file.write_all(bytes)
+        .and_then(|_| file.sync_all())
+        .map_err(|_| SecureMemError::Io)
+}
+
+// A random name in the same directory, so the rename stays on one file system and never overwrites another writer's
+// file
+fn temporary_path(path: &Path) -> Result<PathBuf, SecureMemError> {
+    let name = path.file_name().ok_or(SecureMemError::Io)?;
+    let mut suffix = [0u8; 8];
+    try_fill_random(&mut OsRng, &mut suffix)?;
+
+    let mut temporary = std::ffi::OsString::from(".");
+    temporary.push(name);
+    temporary.push(".");
+    suffix
+        .iter()
+        .for_each(|byte| temporary.push(alloc::format!("{byte:02x}")));
+    temporary.push(".tmp");
+
+    Ok(parent(path).join(temporary))
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
@@ -1,18 +1,180 @@
-use crate::ZeroizeBytes;
+use crate::{
+    page::VaultPages, Cipher, EncryptedMem, XChaCha8Poly1305, ZeroizeArray, ZeroizeBytesArray,
+    SHAMIR_SHARE_LENGTH,
+};
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use chacha20poly1305::{
+    aead::{AeadInPlace, KeyInit, Nonce, Tag},
+    Key,
+};
+#[cfg(feature = "std")]
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use zeroize::{Zeroize, ZeroizeOnDrop};
+#[cfg(feature = "getrandom")]
+use {crate::ZeroizeBytes, chacha20poly1305::aead::bytes::BytesMut};
 
 pub const DEFAULT_VAULT_PAGES: usize = 4;
 pub const DEFAULT_VAULT_PAGE_SIZE: usize = 4096_usize;
 
+pub type KeyId = [u8; 16];
+
+// Bound as associated data so a wrapped key cannot be passed off as an ordinary sealed secret or vice versa
+const WRAPPED_KEY_AAD: &[u8] = b"xor-securemem 2023-01-01 SealingKeyVault wrapped key v1";
+
+// Layout: `WRAPPED_VAULT_MAGIC || WRAPPED_VAULT_VERSION || algorithm || key id || nonce || key commitment ||
+// ciphertext || tag`, everything before the nonce is bound as associated data
+const WRAPPED_VAULT_MAGIC: [u8; 3] = *b"XSK";
+const WRAPPED_VAULT_VERSION: u8 = 1;
+const WRAPPED_VAULT_HEADER_LENGTH: usize =
+    WRAPPED_VAULT_MAGIC.len() + 2 + core::mem::size_of::<KeyId>();
+
+const VAULT_PAGES_FROM_SEED_CONTEXT: &str =
+    "xor-securemem 2023-01-01 SealingKeyVault pages from seed v1";
+
+#[cfg(feature = "argon2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+// The OWASP recommended Argon2id baseline, which is also the `argon2` crate default
+#[cfg(feature = "argon2")]
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Argon2Params {
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+// The OWASP recommended iteration count for PBKDF2-HMAC-SHA256
+#[cfg(feature = "pbkdf2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pbkdf2Params {
+    pub rounds: u32,
+}
+
+#[cfg(feature = "pbkdf2")]
+impl Default for Pbkdf2Params {
+    fn default() -> Self {
+        Pbkdf2Params { rounds: 600_000 }
+    }
+}
+
+#[cfg(feature = "scrypt")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+// N = 2^17, r = 8 and p = 1, the OWASP recommendation and the `scrypt` crate default
+#[cfg(feature = "scrypt")]
+impl Default for ScryptParams {
+    fn default() -> Self {
+        ScryptParams {
+            log_n: scrypt::Params::RECOMMENDED_LOG_N,
+            r: scrypt::Params::RECOMMENDED_R,
+            p: scrypt::Params::RECOMMENDED_P,
+        }
+    }
+}
+
+// Which KDF stretched a passphrase and with what cost, store it next to the salt to derive the same vault again
+#[cfg(any(feature = "argon2", feature = "pbkdf2", feature = "scrypt"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfParams {
+    #[cfg(feature = "argon2")]
+    Argon2id(Argon2Params),
+    #[cfg(feature = "pbkdf2")]
+    Pbkdf2Sha256(Pbkdf2Params),
+    #[cfg(feature = "scrypt")]
+    Scrypt(ScryptParams),
+}
+
+#[cfg(any(feature = "argon2", feature = "pbkdf2", feature = "scrypt"))]
+impl KdfParams {
+    // Stretches straight into an array that is wiped when it drops, also when the KDF fails. argon2 wipes its memory
+    // blocks itself, the PBKDF2 and scrypt working state is dropped as is.
+    fn stretch(
+        &self,
+        passphrase: &[u8],
+        salt: &[u8],
+    ) -> Result<ZeroizeArray<32>, crate::SecureMemError> {
+        let mut stretched = ZeroizeArray::<32>::zeroed();
+        stretched.expose_with_mut(|stretched| match self {
+            #[cfg(feature = "argon2")]
+            KdfParams::Argon2id(params) => {
+                use argon2::{Algorithm, Argon2, Params, Version};
+
+                let params = Params::new(
+                    params.memory_kib,
+                    params.iterations,
+                    params.parallelism,
+                    Some(32),
+                )
+                .map_err(|_| crate::SecureMemError::KeyDerivation)?;
+
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(passphrase, salt, stretched)
+                    .map_err(|_| crate::SecureMemError::KeyDerivation)
+            }
+            #[cfg(feature = "pbkdf2")]
+            KdfParams::Pbkdf2Sha256(params) => {
+                if params.rounds == 0 {
+                    return Err(crate::SecureMemError::KeyDerivation);
+                }
+                pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase, salt, params.rounds, stretched);
+
+                Ok(())
+            }
+            #[cfg(feature = "scrypt")]
+            KdfParams::Scrypt(params) => {
+                let params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+                    .map_err(|_| crate::SecureMemError::KeyDerivation)?;
+
+                scrypt::scrypt(passphrase, salt, &params, stretched)
+                    .map_err(|_| crate::SecureMemError::KeyDerivation)
+            }
+        })?;
+
+        Ok(stretched)
+    }
+}
+
+// 16 random bytes, the length RFC 9106 recommends for Argon2 and the minimum NIST SP 800-132 allows for PBKDF2
+#[cfg(any(feature = "argon2", feature = "pbkdf2", feature = "scrypt"))]
+pub const PASSPHRASE_SALT_LENGTH: usize = 16;
+
+// A fresh salt for the `from_passphrase` constructors. Salts are not secret, store it next to whatever the vault seals
+// so the same key can be derived again after a restart.
+#[cfg(all(
+    feature = "getrandom",
+    any(feature = "argon2", feature = "pbkdf2", feature = "scrypt")
+))]
+pub fn generate_passphrase_salt() -> Result<[u8; PASSPHRASE_SALT_LENGTH], crate::SecureMemError> {
+    let mut salt = [0u8; PASSPHRASE_SALT_LENGTH];
+    crate::store::try_fill_random(&mut rand_core::OsRng, &mut salt)?;
+
+    Ok(salt)
+}
+
+// The pages live in their own page aligned allocation so their address stays stable when the vault is moved, which
+// memory locking and core dump exclusion rely on
 struct SealingKey<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>(
-    [[u8; VAULT_PAGE_SIZE]; VAULT_PAGES],
+    VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>,
 );
 
 impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Zeroize
     for SealingKey<N, VAULT_PAGES, VAULT_PAGE_SIZE>
 {
     fn zeroize(&mut self) {
-        self.0 = [[0u8; VAULT_PAGE_SIZE]; VAULT_PAGES];
+        self.0.iter_mut().for_each(|page| page.zeroize());
     }
 }
 
@@ -25,52 +187,807 @@ impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Dro
     for SealingKey<N, VAULT_PAGES, VAULT_PAGE_SIZE>
 {
     fn drop(&mut self) {
-        self.zeroize()
+        self.zeroize();
+
+        #[cfg(feature = "mlock")]
+        crate::mlock::unlock(self.0.as_ptr());
+    }
+}
+
+impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> core::fmt::Debug
+    for SealingKey<N, VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SealingKey<{N}>([REDACTED])")
     }
 }
 
+// Named keys are boxed so rebalancing the map only moves pointers and never leaves stale copies of key bytes behind
 pub struct SealingKeyVault<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>(
     SealingKey<N, VAULT_PAGES, VAULT_PAGE_SIZE>,
+    BTreeMap<KeyId, Box<ZeroizeArray<32>>>,
 );
 
+// Only the ids of the named keys are shown
+impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> core::fmt::Debug
+    for SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SealingKeyVault")
+            .field("sealing_key", &self.0)
+            .field("named_keys", &self.1.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
 impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>
     SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>
 {
+    const WRAPPED_LENGTH: usize = WRAPPED_VAULT_HEADER_LENGTH
+        + EncryptedMem::<0>::NONCE_LENGTH
+        + crate::KEY_COMMITMENT_LENGTH
+        + VaultPages::<VAULT_PAGES, VAULT_PAGE_SIZE>::SIZE
+        + EncryptedMem::<0>::TAG_LENGTH;
+
+    // Panics when the operating system RNG fails or hands back nothing but zeros
+    #[cfg(feature = "getrandom")]
     pub fn new() -> Self {
-        use nanorand::{ChaCha8, Rng};
+        let mut vault = Self::zeroed();
+        vault.fill_random().expect(crate::store::OS_RNG_FAILED);
 
-        let mut pages = [[0u8; VAULT_PAGE_SIZE]; VAULT_PAGES];
+        vault
+    }
 
-        (0..VAULT_PAGES).for_each(|vault_page_index| {
-            let mut chacha_rng = ChaCha8::new();
-            let mut random_bytes = [0; VAULT_PAGE_SIZE];
-            (0..VAULT_PAGE_SIZE).for_each(|index| {
-                random_bytes[index] = chacha_rng.generate::<u8>();
-            });
+    // The pages are locked before any key material is written into them
+    #[cfg(feature = "mlock")]
+    pub fn try_new_locked() -> Result<Self, crate::SecureMemError> {
+        let mut vault = Self::zeroed();
+        crate::mlock::lock(
+            vault.0 .0.as_ptr(),
+            VaultPages::<VAULT_PAGES, VAULT_PAGE_SIZE>::SIZE,
+        )?;
+        vault.fill_random()?;
 
-            pages[vault_page_index] = random_bytes;
-        });
+        Ok(vault)
+    }
 
-        let prekey = SealingKeyVault(SealingKey(pages));
+    // Argon2id with `Argon2Params::default()`, pass a `ZeroizeBytes` passphrase as `&passphrase.expose()[..]`
+    #[cfg(feature = "argon2")]
+    pub fn from_passphrase(passphrase: &[u8], salt: &[u8]) -> Result<Self, crate::SecureMemError> {
+        Self::from_passphrase_with_params(passphrase, salt, Argon2Params::default())
+    }
 
-        pages.copy_from_slice(&[[0u8; VAULT_PAGE_SIZE]; VAULT_PAGES]); //  TODO CHECK if this is necessary
+    #[cfg(feature = "argon2")]
+    pub fn from_passphrase_with_params(
+        passphrase: &[u8],
+        salt: &[u8],
+        params: Argon2Params,
+    ) -> Result<Self, crate::SecureMemError> {
+        Self::from_passphrase_with_kdf(passphrase, salt, KdfParams::Argon2id(params))
+    }
 
-        prekey
+    // PBKDF2-HMAC-SHA256, for environments that mandate a FIPS approved KDF
+    #[cfg(feature = "pbkdf2")]
+    pub fn from_passphrase_pbkdf2(
+        passphrase: &[u8],
+        salt: &[u8],
+        params: Pbkdf2Params,
+    ) -> Result<Self, crate::SecureMemError> {
+        Self::from_passphrase_with_kdf(passphrase, salt, KdfParams::Pbkdf2Sha256(params))
     }
 
-    pub fn sealing_key(&self) -> ZeroizeBytes {
+    #[cfg(feature = "scrypt")]
+    pub fn from_passphrase_scrypt(
+        passphrase: &[u8],
+        salt: &[u8],
+        params: ScryptParams,
+    ) -> Result<Self, crate::SecureMemError> {
+        Self::from_passphrase_with_kdf(passphrase, salt, KdfParams::Scrypt(params))
+    }
+
+    #[cfg(any(feature = "argon2", feature = "pbkdf2", feature = "scrypt"))]
+    pub fn from_passphrase_with_kdf(
+        passphrase: &[u8],
+        salt: &[u8],
+        kdf: KdfParams,
+    ) -> Result<Self, crate::SecureMemError> {
+        let stretched = kdf.stretch(passphrase, salt)?;
+
+        let mut vault = Self::zeroed();
+        vault.fill_from_seed(stretched.expose_borrowed());
+
+        Ok(vault)
+    }
+
+    fn zeroed() -> Self {
+        let pages = VaultPages::zeroed();
+
+        #[cfg(feature = "dontdump")]
+        crate::page::exclude_from_core_dumps(
+            pages.as_ptr(),
+            VaultPages::<VAULT_PAGES, VAULT_PAGE_SIZE>::SIZE,
+        );
+
+        SealingKeyVault(SealingKey(pages), BTreeMap::new())
+    }
+
+    // Fills the pages in place so no copies of the random bytes are left behind on the stack. An all zero draw can
+    // only come from a broken RNG and would make every vault share one sealing key, so it is rejected.
+    #[cfg(feature = "getrandom")]
+    fn fill_random(&mut self) -> Result<(), crate::SecureMemError> {
+        use rand_core::OsRng;
+
+        self.0
+             .0
+            .iter_mut()
+            .try_for_each(|page| crate::store::try_fill_random(&mut OsRng, page))?;
+
+        let all_zero: bool = self
+            .0
+             .0
+            .iter()
+            .fold(subtle::Choice::from(1), |all_zero, page| {
+                all_zero & crate::store::ct_is_zero(page)
+            })
+            .into();
+        if all_zero && VaultPages::<VAULT_PAGES, VAULT_PAGE_SIZE>::SIZE > 0 {
+            self.0.zeroize();
+
+            return Err(crate::SecureMemError::RandomnessUnavailable);
+        }
+
+        Ok(())
+    }
+
+    // Deterministically expands `seed` over every page so the vault layout is the same as for random vaults
+    #[cfg_attr(
+        not(any(feature = "argon2", feature = "pbkdf2", feature = "scrypt")),
+        allow(dead_code)
+    )]
+    fn fill_from_seed(&mut self, seed: &[u8; 32]) {
+        let mut hasher = blake3::Hasher::new_derive_key(VAULT_PAGES_FROM_SEED_CONTEXT);
+        hasher.update(seed);
+        let mut output = hasher.finalize_xof();
+
+        self.0 .0.iter_mut().for_each(|page| output.fill(page));
+    }
+
+    pub fn sealing_key(&self) -> ZeroizeArray<32> {
         let mut blake3_hasher = blake3::Hasher::new();
 
-        self.0 .0.into_iter().for_each(|page| {
-            blake3_hasher.update(&page);
+        self.0 .0.iter().for_each(|page| {
+            blake3_hasher.update(page);
         });
 
-        let mut key = ZeroizeBytes::new_with_capacity(32);
+        ZeroizeArray::new(*blake3_hasher.finalize().as_bytes())
+    }
+
+    // Fingerprint of the sealing key, for logging which vault sealed a record. Keys held by id are fingerprinted
+    // through `get_key`.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.sealing_key().fingerprint()
+    }
+
+    // Envelope encryption: a per record data key is sealed under the sealing key, which then only ever encrypts keys
+    #[cfg(feature = "getrandom")]
+    pub fn wrap_key(
+        &self,
+        data_key: &ZeroizeArray<32>,
+    ) -> Result<EncryptedMem<32>, crate::SecureMemError> {
+        let mut wrapped = EncryptedMem::new();
+        wrapped.encrypt_with_aad(data_key, self.sealing_key().chacha_key(), WRAPPED_KEY_AAD)?;
+
+        Ok(wrapped)
+    }
+
+    pub fn unwrap_key(
+        &self,
+        wrapped: &EncryptedMem<32>,
+    ) -> Result<ZeroizeArray<32>, crate::SecureMemError> {
+        wrapped.decrypt_with_aad(self.sealing_key().chacha_key(), WRAPPED_KEY_AAD)
+    }
+
+    // Re-wraps every key in `wrapped` under `new_master` and then takes over its sealing key, the named keys of this
+    // vault are kept and those of `new_master` are wiped. All keys are re-wrapped before anything is replaced, so if
+    // any of them fails to unwrap neither the vault nor `wrapped` is changed.
+    #[cfg(feature = "getrandom")]
+    pub fn rotate_master(
+        &mut self,
+        mut new_master: Self,
+        wrapped: &mut [EncryptedMem<32>],
+    ) -> Result<(), crate::SecureMemError> {
+        let rewrapped = wrapped
+            .iter()
+            .map(|wrapped| new_master.wrap_key(&self.unwrap_key(wrapped)?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        wrapped
+            .iter_mut()
+            .zip(rewrapped)
+            .for_each(|(wrapped, rewrapped)| *wrapped = rewrapped);
+        core::mem::swap(&mut self.0, &mut new_master.0);
+
+        Ok(())
+    }
+
+    // Encrypts the vault pages under `kek` for backup, the sealing key is a hash of the pages so they are exported
+    // whole. The key id is the start of the fingerprint. Named keys are not included.
+    #[cfg(feature = "getrandom")]
+    pub fn export_wrapped(&self, kek: &Key) -> Result<ZeroizeBytes, crate::SecureMemError> {
+        type C = XChaCha8Poly1305;
+
+        let nonce: Nonce<C> = crate::store::try_nonce_from_rng(&mut rand_core::OsRng)?;
+        let mut buffer = BytesMut::with_capacity(Self::WRAPPED_LENGTH);
+        buffer.extend_from_slice(&WRAPPED_VAULT_MAGIC);
+        buffer.extend_from_slice(&[WRAPPED_VAULT_VERSION, C::ALGORITHM]);
+        buffer.extend_from_slice(&self.fingerprint()[..core::mem::size_of::<KeyId>()]);
+        buffer.extend_from_slice(&nonce);
+        buffer.extend_from_slice(&crate::store::compute_key_commitment::<C>(kek, &nonce));
+
+        // The capacity is exact, so the pages are only ever copied into this buffer and encrypted there
+        let body = buffer.len();
+        self.0
+             .0
+            .iter()
+            .for_each(|page| buffer.extend_from_slice(page));
+        let (header, pages) = buffer.split_at_mut(body);
+        match C::new(kek).encrypt_in_place_detached(
+            &nonce,
+            &header[..WRAPPED_VAULT_HEADER_LENGTH],
+            pages,
+        ) {
+            Ok(tag) => {
+                buffer.extend_from_slice(tag.as_slice());
+
+                Ok(ZeroizeBytes(buffer))
+            }
+            Err(_) => {
+                crate::store::wipe_bytes_mut(&mut buffer);
+
+                Err(crate::SecureMemError::Encryption)
+            }
+        }
+    }
+
+    // Decrypts in place inside the vault pages. A wrong KEK fails the key commitment with `WrongKey`, a blob altered
+    // anywhere else fails the tag with `Corrupted`.
+    pub fn import_wrapped(blob: &[u8], kek: &Key) -> Result<Self, crate::SecureMemError> {
+        type C = XChaCha8Poly1305;
+
+        if blob.len() != Self::WRAPPED_LENGTH {
+            return Err(crate::SecureMemError::InvalidLength);
+        }
+        let (header, body) = blob.split_at(WRAPPED_VAULT_HEADER_LENGTH);
+        if header[..WRAPPED_VAULT_MAGIC.len()] != WRAPPED_VAULT_MAGIC
+            || header[WRAPPED_VAULT_MAGIC.len()] != WRAPPED_VAULT_VERSION
+        {
+            return Err(crate::SecureMemError::InvalidHeader);
+        }
+        if header[WRAPPED_VAULT_MAGIC.len() + 1] != C::ALGORITHM {
+            return Err(crate::SecureMemError::AlgorithmMismatch);
+        }
+
+        let (nonce, body) = body.split_at(EncryptedMem::<0, C>::NONCE_LENGTH);
+        let nonce = Nonce::<C>::from_slice(nonce);
+        let (key_commitment, body) = body.split_at(crate::KEY_COMMITMENT_LENGTH);
+        let mut expected = crate::store::compute_key_commitment::<C>(kek, nonce);
+        let matches: bool = subtle::ConstantTimeEq::ct_eq(&expected[..], key_commitment).into();
+        expected.zeroize();
+        if !matches {
+            return Err(crate::SecureMemError::WrongKey);
+        }
+
+        let (ciphertext, tag) = body.split_at(VaultPages::<VAULT_PAGES, VAULT_PAGE_SIZE>::SIZE);
+        let mut vault = Self::zeroed();
+        let pages = vault.0 .0.as_flattened_mut();
+        pages.copy_from_slice(ciphertext);
+        if C::new(kek)
+            .decrypt_in_place_detached(nonce, header, pages, Tag::<C>::from_slice(tag))
+            .is_err()
+        {
+            vault.0.zeroize();
+
+            return Err(crate::SecureMemError::Corrupted);
+        }
+
+        Ok(vault)
+    }
+
+    // Splits the sealing key into `shares` shares of which any `threshold` reconstruct it, for M of N escrow
+    #[cfg(feature = "getrandom")]
+    pub fn split(
+        &self,
+        threshold: u8,
+        shares: u8,
+    ) -> Result<Vec<ZeroizeBytesArray<SHAMIR_SHARE_LENGTH>>, crate::SecureMemError> {
+        crate::shamir::split(&self.sealing_key(), threshold, shares)
+    }
+
+    // Recovers the sealing key from at least as many shares as it was split with
+    pub fn reconstruct(
+        shares: &[ZeroizeBytesArray<SHAMIR_SHARE_LENGTH>],
+    ) -> Result<ZeroizeArray<32>, crate::SecureMemError> {
+        crate::shamir::reconstruct(shares)
+    }
+
+    // Replaces and wipes any key already stored under `id`
+    pub fn insert_key(&mut self, id: KeyId, key: ZeroizeArray<32>) -> &mut Self {
+        if let Some(mut displaced) = self.1.insert(id, Box::new(key)) {
+            displaced.zeroize();
+        }
+
+        self
+    }
+
+    pub fn get_key(&self, id: &KeyId) -> Option<&ZeroizeArray<32>> {
+        self.1.get(id).map(|key| &**key)
+    }
+
+    // The removed key is wiped before this returns
+    pub fn remove_key(&mut self, id: &KeyId) -> bool {
+        match self.1.remove(id) {
+            Some(mut removed) => {
+                removed.zeroize();
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    // HKDF-SHA256 with the sealing key as the input keying material, `info` gives each subkey its own domain
+    #[cfg(feature = "hkdf")]
+    pub fn derive_subkey(&self, info: &[u8]) -> ZeroizeArray<32> {
+        self.sealing_key().derive_subkey(None, info)
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Default
+    for SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A vault that many threads can share behind an `Arc`: reads such as fetching the sealing key run concurrently under
+// the read lock, while inserting or removing named keys takes the write lock
+#[cfg(feature = "std")]
+pub struct SharedVault<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>(
+    RwLock<SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>>,
+);
+
+#[cfg(feature = "std")]
+impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>
+    SharedVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    pub fn new(vault: SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>) -> Self {
+        SharedVault(RwLock::new(vault))
+    }
+
+    pub fn read<R>(
+        &self,
+        f: impl FnOnce(&SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>) -> R,
+    ) -> R {
+        f(&self.read_lock())
+    }
+
+    pub fn write<R>(
+        &self,
+        f: impl FnOnce(&mut SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>) -> R,
+    ) -> R {
+        f(&mut self.write_lock())
+    }
+
+    pub fn sealing_key(&self) -> ZeroizeArray<32> {
+        self.read_lock().sealing_key()
+    }
+
+    // A copy is returned because the reference cannot outlive the read lock
+    pub fn get_key(&self, id: &KeyId) -> Option<ZeroizeArray<32>> {
+        self.read_lock().get_key(id).cloned()
+    }
+
+    pub fn insert_key(&self, id: KeyId, key: ZeroizeArray<32>) {
+        self.write_lock().insert_key(id, key);
+    }
+
+    pub fn remove_key(&self, id: &KeyId) -> bool {
+        self.write_lock().remove_key(id)
+    }
+
+    // Every vault operation leaves the vault consistent even if it panics, so a poisoned lock is still safe to use
+    fn read_lock(&self) -> RwLockReadGuard<'_, SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_lock(&self) -> RwLockWriteGuard<'_, SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>
+    From<SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>>
+    for SharedVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn from(vault: SealingKeyVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>) -> Self {
+        SharedVault::new(vault)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize, const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> core::fmt::Debug
+    for SharedVault<N, VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("SharedVault")
+            .field(&*self.read_lock())
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "getrandom"))]
+mod keymaker_tests {
+    use crate::*;
+
+    #[test]
+    fn vault_pages_are_page_aligned() {
+        let vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+
+        assert_eq!(vault.0 .0.as_ptr() as usize % crate::page::page_size(), 0);
+        assert!(vault.0 .0.iter().flatten().any(|byte| *byte != 0));
+    }
+
+    #[test]
+    fn empty_vault_does_not_allocate() {
+        let vault = SealingKeyVault::<32, 0, DEFAULT_VAULT_PAGE_SIZE>::new();
+
+        assert_eq!(vault.sealing_key().expose(), *blake3::hash(b"").as_bytes());
+    }
+
+    #[cfg(feature = "hkdf")]
+    #[test]
+    fn derive_subkey_separates_domains() {
+        let vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+
+        let encryption = vault.derive_subkey(b"encryption");
+        let mac = vault.derive_subkey(b"mac");
+
+        assert!(encryption == vault.derive_subkey(b"encryption"));
+        assert!(encryption != mac);
+        assert!(encryption != vault.sealing_key());
+
+        let other_vault =
+            SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        assert!(encryption != other_vault.derive_subkey(b"encryption"));
+    }
+
+    #[cfg(feature = "pbkdf2")]
+    #[test]
+    fn pbkdf2_matches_published_vectors() {
+        let stretch = |passphrase: &[u8], salt: &[u8], rounds| {
+            KdfParams::Pbkdf2Sha256(Pbkdf2Params { rounds })
+                .stretch(passphrase, salt)
+                .unwrap()
+        };
+
+        // RFC 7914 section 11, then the widely published SHA-256 counterparts of the RFC 6070 vectors
+        assert!(stretch(b"passwd", b"salt", 1).ct_eq_slice(&[
+            0x55, 0xac, 0x04, 0x6e, 0x56, 0xe3, 0x08, 0x9f, 0xec, 0x16, 0x91, 0xc2, 0x25, 0x44,
+            0xb6, 0x05, 0xf9, 0x41, 0x85, 0x21, 0x6d, 0xde, 0x04, 0x65, 0xe6, 0x8b, 0x9d, 0x57,
+            0xc2, 0x0d, 0xac, 0xbc
+        ]));
+        assert!(stretch(b"password", b"salt", 2).ct_eq_slice(&[
+            0xae, 0x4d, 0x0c, 0x95, 0xaf, 0x6b, 0x46, 0xd3, 0x2d, 0x0a, 0xdf, 0xf9, 0x28, 0xf0,
+            0x6d, 0xd0, 0x2a, 0x30, 0x3f, 0x8e, 0xf3, 0xc2, 0x51, 0xdf, 0xd6, 0xe2, 0xd8, 0x5a,
+            0x95, 0x47, 0x4c, 0x43
+        ]));
+        assert!(stretch(b"password", b"salt", 4096).ct_eq_slice(&[
+            0xc5, 0xe4, 0x78, 0xd5, 0x92, 0x88, 0xc8, 0x41, 0xaa, 0x53, 0x0d, 0xb6, 0x84, 0x5c,
+            0x4c, 0x8d, 0x96, 0x28, 0x93, 0xa0, 0x01, 0xce, 0x4e, 0x11, 0xa4, 0x96, 0x38, 0x73,
+            0xaa, 0x98, 0x13, 0x4a
+        ]));
+
+        assert_eq!(
+            KdfParams::Pbkdf2Sha256(Pbkdf2Params { rounds: 0 })
+                .stretch(b"password", b"salt")
+                .err(),
+            Some(SecureMemError::KeyDerivation)
+        );
+    }
+
+    #[cfg(feature = "scrypt")]
+    #[test]
+    fn scrypt_matches_published_vectors() {
+        // RFC 7914 section 12, the first 32 bytes of the 64 byte outputs
+        let stretched = KdfParams::Scrypt(ScryptParams {
+            log_n: 4,
+            r: 1,
+            p: 1,
+        })
+        .stretch(b"", b"")
+        .unwrap();
+        assert!(stretched.ct_eq_slice(&[
+            0x77, 0xd6, 0x57, 0x62, 0x38, 0x65, 0x7b, 0x20, 0x3b, 0x19, 0xca, 0x42, 0xc1, 0x8a,
+            0x04, 0x97, 0xf1, 0x6b, 0x48, 0x44, 0xe3, 0x07, 0x4a, 0xe8, 0xdf, 0xdf, 0xfa, 0x3f,
+            0xed, 0xe2, 0x14, 0x42
+        ]));
+
+        let stretched = KdfParams::Scrypt(ScryptParams {
+            log_n: 10,
+            r: 8,
+            p: 16,
+        })
+        .stretch(b"password", b"NaCl")
+        .unwrap();
+        assert!(stretched.ct_eq_slice(&[
+            0xfd, 0xba, 0xbe, 0x1c, 0x9d, 0x34, 0x72, 0x00, 0x78, 0x56, 0xe7, 0x19, 0x0d, 0x01,
+            0xe9, 0xfe, 0x7c, 0x6a, 0xd7, 0xcb, 0xc8, 0x23, 0x78, 0x30, 0xe7, 0x73, 0x76, 0x63,
+            0x4b, 0x37, 0x31, 0x62
+        ]));
+
+        type Vault = SealingKeyVault<32, 1, DEFAULT_VAULT_PAGE_SIZE>;
+        let params = ScryptParams {
+            log_n: 4,
+            r: 1,
+            p: 1,
+        };
+        let vault = Vault::from_passphrase_scrypt(b"correct horse", b"salt", params).unwrap();
+        let same =
+            Vault::from_passphrase_with_kdf(b"correct horse", b"salt", KdfParams::Scrypt(params))
+                .unwrap();
+        assert!(vault.sealing_key() == same.sealing_key());
+        assert!(
+            vault.sealing_key()
+                != Vault::from_passphrase_scrypt(b"correct horse", b"pepper", params)
+                    .unwrap()
+                    .sealing_key()
+        );
+        assert_eq!(
+            Vault::from_passphrase_scrypt(
+                b"correct horse",
+                b"salt",
+                ScryptParams { r: 0, ..params }
+            )
+            .err(),
+            Some(SecureMemError::KeyDerivation)
+        );
+    }
+
+    #[cfg(feature = "argon2")]
+    #[test]
+    fn generated_salts_reproduce_the_passphrase_vault() {
+        type Vault = SealingKeyVault<32, 1, DEFAULT_VAULT_PAGE_SIZE>;
+
+        let params = Argon2Params {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let salt = generate_passphrase_salt().unwrap();
+        assert!(salt != generate_passphrase_salt().unwrap());
+
+        let passphrase = crate::ZeroizeBytes::from_vec(b"correct horse".to_vec());
+        let vault =
+            Vault::from_passphrase_with_params(&passphrase.expose()[..], &salt, params).unwrap();
+        let restarted =
+            Vault::from_passphrase_with_params(&passphrase.expose()[..], &salt, params).unwrap();
+        let resalted = Vault::from_passphrase_with_params(
+            &passphrase.expose()[..],
+            &generate_passphrase_salt().unwrap(),
+            params,
+        )
+        .unwrap();
+
+        assert!(vault.sealing_key() == restarted.sealing_key());
+        assert!(vault.sealing_key() != resalted.sealing_key());
+    }
+
+    #[cfg(feature = "argon2")]
+    #[test]
+    fn passphrase_vault_is_reproducible() {
+        type Vault = SealingKeyVault<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>;
+
+        let params = Argon2Params {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let salt = b"sixteen byte slt";
+
+        let vault = Vault::from_passphrase_with_params(b"correct horse", salt, params).unwrap();
+        let same = Vault::from_passphrase_with_params(b"correct horse", salt, params).unwrap();
+        let other_salt =
+            Vault::from_passphrase_with_params(b"correct horse", b"another salt!!!!", params)
+                .unwrap();
+        let other_passphrase =
+            Vault::from_passphrase_with_params(b"battery staple", salt, params).unwrap();
+
+        assert!(vault.sealing_key() == same.sealing_key());
+        assert!(vault.sealing_key() != other_salt.sealing_key());
+        assert!(vault.sealing_key() != other_passphrase.sealing_key());
+
+        assert_eq!(
+            Vault::from_passphrase(b"correct horse", b"short").err(),
+            Some(SecureMemError::KeyDerivation)
+        );
+        assert_eq!(
+            Vault::from_passphrase_with_params(
+                b"correct horse",
+                salt,
+                Argon2Params {
+                    memory_kib: 0,
+                    ..params
+                }
+            )
+            .err(),
+            Some(SecureMemError::KeyDerivation)
+        );
+    }
+
+    #[test]
+    fn wrapped_keys_only_unwrap_under_their_vault() {
+        let vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        let data_key = ZeroizeArray::<32>::csprng();
+
+        let wrapped = vault.wrap_key(&data_key).unwrap();
+        assert!(vault.unwrap_key(&wrapped).unwrap() == data_key);
+
+        let other_vault =
+            SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        assert!(other_vault.unwrap_key(&wrapped).is_err());
+
+        // An ordinary secret sealed under the same key is not accepted as a wrapped key
+        let sealed =
+            EncryptedMem::<32>::seal(data_key.clone(), vault.sealing_key().chacha_key()).unwrap();
+        assert!(vault.unwrap_key(&sealed).is_err());
+    }
+
+    #[test]
+    fn rotate_master_rewraps_every_key_or_none() {
+        let mut vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        let id = *b"database-key-v01";
+        vault.insert_key(id, ZeroizeArray::new([0x11; 32]));
+        let data_keys = [0x21u8, 0x22, 0x23].map(|byte| ZeroizeArray::new([byte; 32]));
+        let mut wrapped = data_keys
+            .iter()
+            .map(|data_key| vault.wrap_key(data_key).unwrap())
+            .collect::<Vec<_>>();
+
+        let new_master = SealingKeyVault::new();
+        let new_fingerprint = new_master.fingerprint();
+        vault.rotate_master(new_master, &mut wrapped).unwrap();
+
+        assert_eq!(vault.fingerprint(), new_fingerprint);
+        assert!(vault.get_key(&id).unwrap() == &ZeroizeArray::new([0x11; 32]));
+        wrapped
+            .iter()
+            .zip(&data_keys)
+            .for_each(|(wrapped, data_key)| {
+                assert!(vault.unwrap_key(wrapped).unwrap() == *data_key)
+            });
+
+        // One key wrapped by another vault fails the rotation and leaves everything as it was
+        let stranger = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        wrapped.push(stranger.wrap_key(&data_keys[0]).unwrap());
+        let before = wrapped
+            .iter()
+            .map(|wrapped| wrapped.to_bytes())
+            .collect::<Vec<_>>();
+
+        assert!(vault
+            .rotate_master(SealingKeyVault::new(), &mut wrapped)
+            .is_err());
+        assert_eq!(vault.fingerprint(), new_fingerprint);
+        wrapped
+            .iter()
+            .zip(&before)
+            .for_each(|(wrapped, before)| assert!(wrapped.to_bytes() == *before));
+    }
+
+    #[test]
+    fn wrapped_exports_import_only_under_their_kek() {
+        type Vault = SealingKeyVault<32, 1, DEFAULT_VAULT_PAGE_SIZE>;
+        let mut vault = Vault::new();
+        vault.insert_key(*b"database-key-v01", ZeroizeArray::new([0x11; 32]));
+        let kek = ZeroizeArray::<32>::csprng();
+
+        let blob = vault.export_wrapped(kek.chacha_key()).unwrap();
+        let imported = Vault::import_wrapped(&blob.expose()[..], kek.chacha_key()).unwrap();
+        assert!(imported.sealing_key() == vault.sealing_key());
+        assert_eq!(&blob.expose()[4..5], &[XChaCha8Poly1305::ALGORITHM]);
+        assert_eq!(&blob.expose()[5..21], &vault.fingerprint()[..16]);
+        assert!(imported.get_key(b"database-key-v01").is_none());
+
+        // Each export draws a fresh nonce
+        assert!(vault.export_wrapped(kek.chacha_key()).unwrap() != blob);
+
+        let wrong_kek = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            Vault::import_wrapped(&blob.expose()[..], wrong_kek.chacha_key()).err(),
+            Some(SecureMemError::WrongKey)
+        );
+
+        // The key id is associated data and the last byte belongs to the tag
+        [5, blob.len() / 2, blob.len() - 1]
+            .iter()
+            .for_each(|position| {
+                let mut tampered = blob.expose().to_vec();
+                tampered[*position] ^= 1;
+                assert_eq!(
+                    Vault::import_wrapped(&tampered, kek.chacha_key()).err(),
+                    Some(SecureMemError::Corrupted)
+                );
+            });
+
+        let mut renamed = blob.expose().to_vec();
+        renamed[0] = b'Y';
+        assert_eq!(
+            Vault::import_wrapped(&renamed, kek.chacha_key()).err(),
+            Some(SecureMemError::InvalidHeader)
+        );
+        assert_eq!(
+            Vault::import_wrapped(&blob.expose()[1..], kek.chacha_key()).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert_eq!(
+            SealingKeyVault::<32, 2, DEFAULT_VAULT_PAGE_SIZE>::import_wrapped(
+                &blob.expose()[..],
+                kek.chacha_key()
+            )
+            .err(),
+            Some(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn named_keys_are_stored_and_removed() {
+        let mut vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        let id = *b"database-key-v01";
+
+        assert!(vault.get_key(&id).is_none());
+        vault.insert_key(id, ZeroizeArray::new([1u8; 32]));
+        vault.insert_key(id, ZeroizeArray::new([2u8; 32]));
+        assert_eq!(vault.get_key(&id).unwrap().expose_borrowed(), &[2u8; 32]);
+        assert!(vault.get_key(&[0u8; 16]).is_none());
+
+        assert!(vault.remove_key(&id));
+        assert!(!vault.remove_key(&id));
+        assert!(vault.get_key(&id).is_none());
+    }
+
+    #[cfg(all(feature = "dontdump", target_os = "linux"))]
+    #[test]
+    fn vault_pages_are_excluded_from_core_dumps() {
+        let vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        let address = vault.0 .0.as_ptr() as usize;
+
+        let smaps = std::fs::read_to_string("/proc/self/smaps").unwrap();
+        let mut in_mapping = false;
+        let vm_flags = smaps
+            .lines()
+            .find_map(|line| {
+                if let Some((range, _)) = line.split_once(' ') {
+                    if let Some((start, end)) = range.split_once('-') {
+                        if let (Ok(start), Ok(end)) = (
+                            usize::from_str_radix(start, 16),
+                            usize::from_str_radix(end, 16),
+                        ) {
+                            in_mapping = (start..end).contains(&address);
+                            return None;
+                        }
+                    }
+                }
 
-        key.set(aead::bytes::BytesMut::from(
-            blake3_hasher.finalize().as_bytes().as_slice(),
-        ));
+                match in_mapping {
+                    true => line.strip_prefix("VmFlags:").map(str::to_owned),
+                    false => None,
+                }
+            })
+            .unwrap();
 
-        key
+        assert!(vm_flags.split_whitespace().any(|flag| flag == "dd"));
     }
 }
@@ -1,15 +1,92 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod cipher;
+pub use cipher::*;
+mod error;
+pub use error::*;
 mod store;
 pub use store::*;
+mod stack;
+pub use stack::*;
+mod detached;
+pub use detached::*;
+mod stream;
+pub use stream::*;
+mod nonce;
+pub use nonce::*;
+mod sealer;
+pub use sealer::*;
+mod shamir;
+pub use shamir::SHAMIR_SHARE_LENGTH;
+mod token;
+pub use token::*;
+mod vec;
+pub use vec::*;
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "hmac")]
+mod mac;
+#[cfg(feature = "hmac")]
+pub use mac::*;
 mod keymaker;
 pub use keymaker::*;
+#[cfg(any(feature = "hex", feature = "base64"))]
+mod encoding;
+#[cfg(feature = "base64")]
+pub use encoding::Base64Alphabet;
+#[cfg(feature = "mlock")]
+mod mlock;
+#[cfg(feature = "mnemonic")]
+mod mnemonic;
+mod page;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "aes-siv")]
+mod siv;
+#[cfg(feature = "aes-siv")]
+pub use siv::*;
+#[cfg(feature = "bytemuck")]
+mod value;
+pub use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "bytemuck")]
+pub use value::*;
+#[cfg(feature = "x25519")]
+mod x25519;
+#[cfg(feature = "x25519")]
+pub use x25519::*;
 
 // TODO Test different nonces
 // TODO Test different cipher and plaintext
 
-#[cfg(test)]
+// Decodes the published test vectors, kept outside `sanity_tests` because the x25519 tests run without getrandom
+#[cfg(all(
+    test,
+    any(feature = "x25519", all(feature = "hmac", feature = "getrandom"))
+))]
+fn hex(encoded: &str) -> Vec<u8> {
+    (0..encoded.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&encoded[index..index + 2], 16).unwrap())
+        .collect()
+}
+
+// These tests draw keys and nonces from the operating system RNG
+#[cfg(all(test, feature = "getrandom"))]
 mod sanity_tests {
     use crate::*;
 
+    // With key commitments a wrong key is reported before the AEAD runs and a failed tag check means corruption
+    const WRONG_KEY: SecureMemError = match cfg!(feature = "key-commitment") {
+        true => SecureMemError::WrongKey,
+        false => SecureMemError::AuthenticationFailed,
+    };
+    const TAMPERED: SecureMemError = match cfg!(feature = "key-commitment") {
+        true => SecureMemError::Corrupted,
+        false => SecureMemError::AuthenticationFailed,
+    };
+
     #[test]
     fn correctness_test() {
         let sealing_vault =
@@ -18,17 +95,2330 @@ mod sanity_tests {
         let mut store = EncryptedMem::<32>::new();
         let plaintext = ZeroizeArray::new([4u8; 32]);
 
-        store.encrypt(&plaintext, sealing_vault.sealing_key().chacha_key());
+        store
+            .encrypt(&plaintext, sealing_vault.sealing_key().chacha_key())
+            .unwrap();
+
+        dbg!(&store);
+
+        let decrypted = store
+            .decrypt(sealing_vault.sealing_key().chacha_key())
+            .unwrap();
+
+        assert_eq!(plaintext.expose_borrowed(), decrypted.expose_borrowed());
+    }
+
+    #[test]
+    fn wrong_key_fails_authentication() {
+        let mut store = EncryptedMem::<32>::new();
+        let plaintext = ZeroizeArray::new([4u8; 32]);
+        let key = ZeroizeArray::<32>::csprng();
+        let wrong_key = ZeroizeArray::<32>::csprng();
+
+        store.encrypt(&plaintext, key.chacha_key()).unwrap();
+
+        assert_eq!(store.decrypt(wrong_key.chacha_key()).err(), Some(WRONG_KEY));
+        assert!(store.decrypt(key.chacha_key()).is_ok());
+    }
+
+    #[test]
+    fn decrypt_before_encrypt_is_invalid_length() {
+        let store = EncryptedMem::<32>::new();
+        let key = ZeroizeArray::<32>::csprng();
+
+        assert_eq!(
+            store.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn ciphertext_for_another_size_is_invalid_length() {
+        let key = ZeroizeArray::<32>::csprng();
+        let small =
+            EncryptedMem::<16>::seal(ZeroizeArray::new([0x2au8; 16]), key.chacha_key()).unwrap();
+
+        // A 16 byte secret's ciphertext and nonce moved into a store for 32 byte secrets
+        let mut ciphertext = ZeroizeBytesArray::<32>::new();
+        ciphertext.set(small.ciphertext().expose().clone());
+        let store = EncryptedMem::<32>::from_parts(ciphertext, *small.nonce());
+
+        assert_eq!(
+            store.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        let mut out = ZeroizeArray::<32>::zeroed();
+        assert_eq!(
+            store.decrypt_into(key.chacha_key(), &mut out).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn error_display_does_not_leak_bytes() {
+        let errors = [
+            SecureMemError::Encryption,
+            SecureMemError::AuthenticationFailed,
+            SecureMemError::InvalidLength,
+            SecureMemError::InvalidHeader,
+            SecureMemError::IndexOutOfBounds,
+            SecureMemError::WrongKey,
+            SecureMemError::Corrupted,
+            SecureMemError::MemoryLock,
+            SecureMemError::KeyDerivation,
+            SecureMemError::AlgorithmMismatch,
+            SecureMemError::InvalidNonce,
+            SecureMemError::InvalidString,
+            SecureMemError::RandomnessUnavailable,
+            SecureMemError::CounterExhausted,
+            SecureMemError::InvalidEncoding,
+            SecureMemError::InvalidShares,
+            SecureMemError::InvalidAlphabet,
+            SecureMemError::Io,
+            SecureMemError::InvalidPublicKey,
+            SecureMemError::UnknownWord,
+            SecureMemError::InvalidChecksum,
+        ];
+
+        errors.iter().for_each(|error| {
+            let displayed = format!("{error} {error:?}");
+            assert!(!displayed
+                .chars()
+                .any(|character| character.is_ascii_digit()));
+        });
+
+        let mismatch = SecureMemError::LengthMismatch {
+            expected: 32,
+            actual: 16,
+        };
+        assert_eq!(
+            mismatch.to_string(),
+            "length mismatch: expected 32 bytes, got 16"
+        );
+
+        let boxed: Box<dyn std::error::Error> = Box::new(SecureMemError::AuthenticationFailed);
+        assert!(boxed.to_string().contains("authentication failed"));
+    }
+
+    #[test]
+    fn repeated_encrypt_changes_public_nonce() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        let plaintext = ZeroizeArray::new([9u8; 32]);
+
+        store.encrypt(&plaintext, key.chacha_key()).unwrap();
+        let first_nonce = *store.nonce();
+        store.encrypt(&plaintext, key.chacha_key()).unwrap();
+
+        assert_ne!(&first_nonce, store.nonce());
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            plaintext.expose_borrowed()
+        );
+    }
+
+    #[test]
+    fn from_parts_reconstructs_a_decryptable_store() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintext = ZeroizeArray::new([6u8; 32]);
+
+        let mut original = EncryptedMem::<32>::new();
+        original.encrypt(&plaintext, key.chacha_key()).unwrap();
+
+        let restored =
+            EncryptedMem::<32>::from_parts(original.ciphertext().clone(), *original.nonce());
+        drop(original);
+
+        assert_eq!(
+            restored
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            plaintext.expose_borrowed()
+        );
+    }
+
+    #[test]
+    fn associated_data_must_match_on_decrypt() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintext = ZeroizeArray::new([8u8; 32]);
+        let mut store = EncryptedMem::<32>::new();
+
+        store
+            .encrypt_with_aad(&plaintext, key.chacha_key(), b"record-1")
+            .unwrap();
+
+        assert_eq!(
+            store
+                .decrypt_with_aad(key.chacha_key(), b"record-1")
+                .unwrap()
+                .expose_borrowed(),
+            plaintext.expose_borrowed()
+        );
+        assert_eq!(
+            store.decrypt_with_aad(key.chacha_key(), b"record-2").err(),
+            Some(TAMPERED)
+        );
+        assert_eq!(store.decrypt(key.chacha_key()).err(), Some(TAMPERED));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_decrypts() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintext = ZeroizeArray::new([0x42u8; 32]);
+        let mut store = EncryptedMem::<32>::new();
+        store.encrypt(&plaintext, key.chacha_key()).unwrap();
+
+        let serialized = serde_json::to_string(&store).unwrap();
+        let restored: EncryptedMem<32> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(restored.nonce(), store.nonce());
+        assert_eq!(
+            restored
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            plaintext.expose_borrowed()
+        );
+        assert!(serde_json::from_str::<EncryptedMem<16>>(&serialized).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips_through_a_compact_format() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([0x24u8; 32]), key.chacha_key())
+            .unwrap();
+
+        let serialized = bincode::serialize(&store).unwrap();
+        let restored: EncryptedMem<32> = bincode::deserialize(&serialized).unwrap();
+
+        assert_eq!(restored.nonce(), store.nonce());
+        assert_eq!(
+            restored
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            &[0x24u8; 32]
+        );
+        assert!(bincode::deserialize::<EncryptedMem<16>>(&serialized).is_err());
+        assert!(
+            bincode::deserialize::<EncryptedMem<32>>(&serialized[..serialized.len() - 1]).is_err()
+        );
+    }
+
+    #[test]
+    fn try_chacha_key_rejects_wrong_lengths() {
+        let empty = ZeroizeBytes::new();
+        assert_eq!(
+            empty.try_chacha_key().err(),
+            Some(SecureMemError::InvalidLength)
+        );
+
+        let short = ZeroizeBytes::csprng::<16>();
+        assert_eq!(
+            short.try_chacha_key().err(),
+            Some(SecureMemError::InvalidLength)
+        );
+
+        let key = ZeroizeBytes::csprng::<32>();
+        assert!(key.try_chacha_key().is_ok());
+
+        let long_array = ZeroizeBytesArray::<33>::csprng();
+        assert_eq!(
+            long_array.try_chacha_key().err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert!(ZeroizeBytesArray::<32>::csprng().try_chacha_key().is_ok());
+    }
+
+    #[test]
+    fn wire_format_round_trips() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintext = ZeroizeArray::new([0x17u8; 32]);
+        let mut store = EncryptedMem::<32>::new();
+        store.encrypt(&plaintext, key.chacha_key()).unwrap();
+
+        let bytes = store.to_bytes();
+        let key_commitment_length = store.key_commitment().map_or(0, |_| KEY_COMMITMENT_LENGTH);
+        assert_eq!(
+            bytes.expose().len(),
+            WIRE_HEADER_LENGTH + XNONCE_LENGTH + key_commitment_length + 32 + TAG_LENGTH
+        );
+        assert_eq!(&bytes.expose()[..WIRE_MAGIC.len()], &WIRE_MAGIC);
+
+        let restored = EncryptedMem::<32>::from_bytes(bytes.expose()).unwrap();
+        assert_eq!(restored.nonce(), store.nonce());
+        assert_eq!(
+            restored
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            plaintext.expose_borrowed()
+        );
+    }
+
+    #[test]
+    fn wire_format_rejects_malformed_input() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([1u8; 32]), key.chacha_key())
+            .unwrap();
+        let bytes = store.to_bytes();
+        let bytes = &bytes.expose()[..];
+
+        assert_eq!(
+            EncryptedMem::<32>::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert_eq!(
+            EncryptedMem::<32>::from_bytes(&bytes[..WIRE_HEADER_LENGTH + XNONCE_LENGTH]).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert_eq!(
+            EncryptedMem::<16>::from_bytes(bytes).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+
+        let mut bad_magic = bytes.to_vec();
+        bad_magic[0] ^= 0xFF;
+        assert_eq!(
+            EncryptedMem::<32>::from_bytes(&bad_magic).err(),
+            Some(SecureMemError::InvalidHeader)
+        );
+
+        let mut bad_version = bytes.to_vec();
+        bad_version[WIRE_MAGIC.len()] = 0xFF;
+        assert_eq!(
+            EncryptedMem::<32>::from_bytes(&bad_version).err(),
+            Some(SecureMemError::InvalidHeader)
+        );
+
+        // A corrupted body still parses but is caught by authentication
+        let mut corrupted = bytes.to_vec();
+        *corrupted.last_mut().unwrap() ^= 0x01;
+        let corrupted = EncryptedMem::<32>::from_bytes(&corrupted).unwrap();
+        assert_eq!(corrupted.decrypt(key.chacha_key()).err(), Some(TAMPERED));
+    }
+
+    fn check_bounds<const N: usize>() {
+        let mut array = ZeroizeArray::<N>::zeroed();
+
+        if N > 0 {
+            array.insert(N - 1, 0xAB).unwrap();
+            assert_eq!(array.get(N - 1), Some(0xAB));
+        }
+
+        assert_eq!(
+            array.insert(N, 0xCD).err(),
+            Some(SecureMemError::IndexOutOfBounds)
+        );
+        assert_eq!(
+            array.insert(usize::MAX, 0xCD).err(),
+            Some(SecureMemError::IndexOutOfBounds)
+        );
+        assert_eq!(array.get(N), None);
+        assert_eq!(array.get(usize::MAX), None);
+    }
+
+    #[test]
+    fn insert_and_get_are_bounds_checked() {
+        check_bounds::<0>();
+        check_bounds::<1>();
+        check_bounds::<16>();
+        check_bounds::<32>();
+        check_bounds::<64>();
+
+        let mut array = ZeroizeArray::<4>::zeroed();
+        array.insert(0, 1).unwrap().insert(3, 4).unwrap();
+        assert_eq!(array.expose(), [1, 0, 0, 4]);
+    }
+
+    #[test]
+    fn zeroize_array_equality_is_constant_time() {
+        let secret = ZeroizeArray::new([0x5Au8; 32]);
+        let same = ZeroizeArray::new([0x5Au8; 32]);
+
+        let mut first_differs = [0x5Au8; 32];
+        first_differs[0] ^= 1;
+        let first_differs = ZeroizeArray::new(first_differs);
+
+        let mut last_differs = [0x5Au8; 32];
+        last_differs[31] ^= 1;
+        let last_differs = ZeroizeArray::new(last_differs);
+
+        assert!(bool::from(secret.ct_eq(&same)));
+        assert!(!bool::from(secret.ct_eq(&first_differs)));
+        assert!(!bool::from(secret.ct_eq(&last_differs)));
+
+        assert!(secret == same);
+        assert!(secret != first_differs);
+        assert!(secret != last_differs);
+    }
+
+    #[test]
+    fn byte_buffer_equality_is_constant_time() {
+        use chacha20poly1305::aead::bytes::BytesMut;
+
+        let bytes = |data: &[u8]| {
+            let mut buffer = ZeroizeBytes::new();
+            buffer.set(BytesMut::from(data));
+            buffer
+        };
+        let secret = bytes(b"a shared secret");
+
+        assert!(bool::from(secret.ct_eq(&bytes(b"a shared secret"))));
+        assert!(secret == bytes(b"a shared secret"));
+        assert!(secret != bytes(b"b shared secret"));
+        assert!(secret != bytes(b"a shared secreu"));
+        assert!(secret != bytes(b"a shared"));
+        assert!(secret != bytes(b"a shared secret!"));
+
+        let mut array = ZeroizeBytesArray::<4>::new();
+        array.set(BytesMut::from(&[1u8, 2, 3, 4][..]));
+        let mut last_differs = ZeroizeBytesArray::<4>::new();
+        last_differs.set(BytesMut::from(&[1u8, 2, 3, 5][..]));
+
+        assert!(array == array.clone());
+        assert!(array != last_differs);
+    }
+
+    #[cfg(feature = "mlock")]
+    #[test]
+    fn locked_vault_derives_the_same_kind_of_key() {
+        let vault =
+            SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::try_new_locked()
+                .unwrap();
+        let plaintext = ZeroizeArray::new([3u8; 32]);
+        let mut store = EncryptedMem::<32>::new();
+
+        store
+            .encrypt(&plaintext, vault.sealing_key().chacha_key())
+            .unwrap();
+
+        assert_eq!(
+            store
+                .decrypt(vault.sealing_key().chacha_key())
+                .unwrap()
+                .expose(),
+            plaintext.expose()
+        );
+    }
+
+    #[test]
+    fn decrypts_concurrently_through_a_shared_reference() {
+        use std::{sync::Arc, thread};
+
+        let key = Arc::new(ZeroizeArray::<32>::csprng());
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([0x33u8; 32]), key.chacha_key())
+            .unwrap();
+        let store = Arc::new(store);
+
+        let readers = (0..2)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                let key = Arc::clone(&key);
+
+                thread::spawn(move || {
+                    (0..64)
+                        .all(|_| store.decrypt(key.chacha_key()).unwrap().expose() == [0x33u8; 32])
+                })
+            })
+            .collect::<Vec<_>>();
+
+        readers
+            .into_iter()
+            .for_each(|reader| assert!(reader.join().unwrap()));
+    }
+
+    #[test]
+    fn associated_data_binds_ciphertext_to_its_slot() {
+        let key = ZeroizeArray::<32>::csprng();
+        let aad = b"user:1234/field:api-token";
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt_with_aad(&ZeroizeArray::new([0x44u8; 32]), key.chacha_key(), aad)
+            .unwrap();
+
+        let bytes = store.to_bytes();
+        assert!(!bytes
+            .expose()
+            .windows(aad.len())
+            .any(|window| window == aad));
+
+        let moved = EncryptedMem::<32>::from_bytes(bytes.expose()).unwrap();
+        assert_eq!(
+            moved
+                .decrypt_with_aad(key.chacha_key(), b"user:5678/field:api-token")
+                .err(),
+            Some(TAMPERED)
+        );
+        assert_eq!(
+            moved
+                .decrypt_with_aad(key.chacha_key(), aad)
+                .unwrap()
+                .expose(),
+            [0x44u8; 32]
+        );
+    }
+
+    #[test]
+    fn decrypt_with_scopes_the_plaintext() {
+        let key = ZeroizeArray::<32>::csprng();
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([0x21u8; 32]), key.chacha_key())
+            .unwrap();
+
+        let first = store
+            .decrypt_with(key.chacha_key(), |plaintext| plaintext[0])
+            .unwrap();
+        assert_eq!(first, 0x21);
+
+        let mut called = false;
+        assert_eq!(
+            store
+                .decrypt_with(wrong_key.chacha_key(), |_| called = true)
+                .err(),
+            Some(WRONG_KEY)
+        );
+        assert!(!called);
+    }
+
+    fn round_trip_with<C: Cipher>() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32, C>::new();
+        let plaintext = ZeroizeArray::new([9u8; 32]);
+
+        store.encrypt(&plaintext, key.chacha_key()).unwrap();
+
+        assert_eq!(store.nonce().len(), EncryptedMem::<32, C>::NONCE_LENGTH);
+        assert_eq!(
+            store.ciphertext().expose().len(),
+            32 + EncryptedMem::<32, C>::TAG_LENGTH
+        );
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            plaintext.expose_borrowed()
+        );
+    }
+
+    #[test]
+    fn every_cipher_round_trips() {
+        round_trip_with::<XChaCha8Poly1305>();
+        round_trip_with::<XChaCha12Poly1305>();
+        round_trip_with::<XChaCha20Poly1305>();
+        #[cfg(feature = "aes-gcm")]
+        round_trip_with::<Aes256Gcm>();
+    }
+
+    #[test]
+    fn wire_format_records_the_algorithm() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32, XChaCha20Poly1305>::new();
+        store
+            .encrypt(&ZeroizeArray::new([8u8; 32]), key.chacha_key())
+            .unwrap();
+        let bytes = store.to_bytes();
+
+        assert_eq!(
+            wire_algorithm(bytes.expose()),
+            Ok(XChaCha20Poly1305::ALGORITHM)
+        );
+        assert_eq!(
+            EncryptedMem::<32, XChaCha12Poly1305>::from_bytes(bytes.expose()).err(),
+            Some(SecureMemError::AlgorithmMismatch)
+        );
+        assert!(EncryptedMem::<32, XChaCha20Poly1305>::from_bytes(bytes.expose()).is_ok());
+        assert_eq!(
+            wire_algorithm(b"XS").err(),
+            Some(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn xchacha20_interoperates_with_the_plain_aead() {
+        use chacha20poly1305::aead::{bytes::BytesMut, AeadInPlace, KeyInit};
+
+        let key = ZeroizeArray::<32>::csprng();
+        let nonce = chacha20poly1305::XNonce::from([7u8; 24]);
+        let mut sealed = BytesMut::from(&[5u8; 32][..]);
+        XChaCha20Poly1305::new(key.chacha_key())
+            .encrypt_in_place(&nonce, b"", &mut sealed)
+            .unwrap();
+
+        let mut ciphertext = ZeroizeBytesArray::<32>::with_capacity(32 + TAG_LENGTH);
+        ciphertext.set(sealed);
+        let store = EncryptedMem::<32, XChaCha20Poly1305>::from_parts(ciphertext, nonce);
+
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[5u8; 32]
+        );
+        // The 8 round default cannot open a 20 round ciphertext
+        let mut reduced_ciphertext = ZeroizeBytesArray::<32>::with_capacity(32 + TAG_LENGTH);
+        reduced_ciphertext.set(store.ciphertext().expose().clone());
+        let reduced = EncryptedMem::<32>::from_parts(reduced_ciphertext, *store.nonce());
+        assert_eq!(
+            reduced.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn decrypt_into_zeroes_out_on_failure() {
+        let key = ZeroizeArray::<32>::csprng();
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([3u8; 32]), key.chacha_key())
+            .unwrap();
+
+        let mut out = ZeroizeArray::new([0xAAu8; 32]);
+        assert_eq!(
+            store.decrypt_into(wrong_key.chacha_key(), &mut out).err(),
+            Some(WRONG_KEY)
+        );
+        assert_eq!(out.expose_borrowed(), &[0u8; 32]);
+
+        store.decrypt_into(key.chacha_key(), &mut out).unwrap();
+        assert_eq!(out.expose_borrowed(), &[3u8; 32]);
+    }
+
+    #[test]
+    fn seal_consumes_the_plaintext() {
+        let key = ZeroizeArray::<32>::csprng();
+        let sealed =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([6u8; 32]), key.chacha_key()).unwrap();
+
+        assert_eq!(
+            sealed.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[6u8; 32]
+        );
+
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt_owned(ZeroizeArray::new([7u8; 32]), key.chacha_key())
+            .unwrap();
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[7u8; 32]
+        );
+    }
+
+    #[test]
+    fn builder_opens_an_externally_sealed_ciphertext() {
+        use chacha20poly1305::aead::{bytes::BytesMut, AeadInPlace, KeyInit};
+
+        let key = ZeroizeArray::<32>::csprng();
+        let nonce = chacha20poly1305::XNonce::from([0x42u8; 24]);
+        let mut sealed = BytesMut::from(&[0x24u8; 32][..]);
+        XChaCha8Poly1305::new(key.chacha_key())
+            .encrypt_in_place(&nonce, b"", &mut sealed)
+            .unwrap();
+        let mut ciphertext = ZeroizeBytesArray::<32>::with_capacity(32 + TAG_LENGTH);
+        ciphertext.set(sealed);
+
+        let store = EncryptedMem::<32>::builder()
+            .nonce(nonce)
+            .ciphertext(ciphertext)
+            .build()
+            .unwrap();
+
+        assert_eq!(store.nonce(), &nonce);
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[0x24u8; 32]
+        );
+    }
+
+    #[test]
+    fn builder_rejects_a_zero_nonce_unless_allowed() {
+        let zero_nonce = chacha20poly1305::XNonce::default();
+
+        assert!(matches!(
+            EncryptedMem::<32>::builder().nonce(zero_nonce).build(),
+            Err(SecureMemError::InvalidNonce)
+        ));
+        assert!(EncryptedMem::<32>::builder()
+            .nonce(zero_nonce)
+            .allow_zero_nonce()
+            .build()
+            .is_ok());
+
+        let store = EncryptedMem::<32>::builder().capacity(64).build().unwrap();
+        assert!(store.ciphertext().expose().capacity() >= 32 + 64);
+    }
+
+    #[test]
+    fn rotate_key_moves_the_ciphertext_to_the_new_key() {
+        let old_key = ZeroizeArray::<32>::csprng();
+        let new_key = ZeroizeArray::<32>::csprng();
+        let mut store =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x5Au8; 32]), old_key.chacha_key())
+                .unwrap();
+        let old_nonce = *store.nonce();
+        let old_ciphertext = store.ciphertext().expose().clone();
+
+        // A wrong old key leaves the ciphertext intact and still decryptable with the real old key
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            store
+                .rotate_key(wrong_key.chacha_key(), new_key.chacha_key())
+                .err(),
+            Some(WRONG_KEY)
+        );
+        assert_eq!(store.nonce(), &old_nonce);
+        assert_eq!(store.ciphertext().expose(), &old_ciphertext);
+        assert!(store.decrypt(old_key.chacha_key()).is_ok());
 
-        dbg!(&store.ciphertext().expose().as_ref());
+        store
+            .rotate_key(old_key.chacha_key(), new_key.chacha_key())
+            .unwrap();
 
-        let decrypted = store.decrypt(sealing_vault.sealing_key().chacha_key());
+        assert_ne!(store.nonce(), &old_nonce);
+        assert_ne!(store.ciphertext().expose(), &old_ciphertext);
+        assert_eq!(
+            store
+                .decrypt(new_key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            &[0x5Au8; 32]
+        );
+        assert_eq!(store.decrypt(old_key.chacha_key()).err(), Some(WRONG_KEY));
+    }
 
-        let decrypted: [u8; 32] = decrypted[..].try_into().unwrap();
+    fn cross_check<A: Cipher, B: Cipher>() {
+        let key = ZeroizeArray::<32>::csprng();
+        let sealed = EncryptedMem::<32, A>::seal(ZeroizeArray::new([0x11u8; 32]), key.chacha_key())
+            .unwrap()
+            .to_bytes();
 
         assert_eq!(
-            &plaintext.expose_borrowed().as_slice(),
-            &decrypted.as_slice()
+            EncryptedMem::<32, B>::from_bytes(sealed.expose()).err(),
+            Some(SecureMemError::AlgorithmMismatch)
         );
     }
+
+    #[test]
+    fn ciphertexts_do_not_cross_suites() {
+        cross_check::<XChaCha8Poly1305, XChaCha12Poly1305>();
+        cross_check::<XChaCha12Poly1305, XChaCha20Poly1305>();
+        cross_check::<XChaCha20Poly1305, XChaCha8Poly1305>();
+        #[cfg(feature = "aes-gcm")]
+        {
+            cross_check::<Aes256Gcm, XChaCha20Poly1305>();
+            cross_check::<XChaCha8Poly1305, Aes256Gcm>();
+        }
+
+        // Even with the header stripped, the same bytes fail authentication under another cipher
+        let key = ZeroizeArray::<32>::csprng();
+        let sealed = EncryptedMem::<32, XChaCha12Poly1305>::seal(
+            ZeroizeArray::new([0x11u8; 32]),
+            key.chacha_key(),
+        )
+        .unwrap();
+        let mut ciphertext = ZeroizeBytesArray::<32>::with_capacity(32 + TAG_LENGTH);
+        ciphertext.set(sealed.ciphertext().expose().clone());
+        let other = EncryptedMem::<32, XChaCha20Poly1305>::from_parts(ciphertext, *sealed.nonce());
+        assert_eq!(
+            other.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn expose_with_scopes_access_to_the_secret() {
+        let mut secret = ZeroizeArray::new([3u8; 32]);
+
+        assert_eq!(
+            secret.expose_with(|bytes| bytes.iter().map(|byte| *byte as usize).sum::<usize>()),
+            96
+        );
+
+        secret.expose_with_mut(|bytes| bytes[0] = 0xFF);
+        assert_eq!(secret.get(0), Some(0xFF));
+        assert_eq!(secret.get(1), Some(3));
+    }
+
+    #[test]
+    fn encrypted_bytes_round_trip_every_length() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedBytes::<XChaCha8Poly1305>::new();
+
+        [0usize, 1, 31, 32, 33, 4096, 3].iter().for_each(|length| {
+            let mut plaintext = ZeroizeBytes::new();
+            plaintext.set((0..*length).map(|byte| byte as u8).collect::<Vec<u8>>()[..].into());
+
+            store.encrypt(&plaintext, key.chacha_key()).unwrap();
+            assert_eq!(
+                store.ciphertext().expose().len(),
+                length + EncryptedBytes::<XChaCha8Poly1305>::TAG_LENGTH
+            );
+
+            let decrypted = store.decrypt(key.chacha_key()).unwrap();
+            assert_eq!(&decrypted.expose()[..], &plaintext.expose()[..]);
+        });
+
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            store.decrypt(wrong_key.chacha_key()).err().unwrap(),
+            WRONG_KEY
+        );
+        assert_eq!(
+            EncryptedBytes::<XChaCha8Poly1305>::new()
+                .decrypt(key.chacha_key())
+                .err()
+                .unwrap(),
+            SecureMemError::InvalidLength
+        );
+    }
+
+    #[test]
+    fn encrypted_string_round_trips_and_validates_utf8() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut token = EncryptedString::<XChaCha8Poly1305>::new();
+        token
+            .encrypt(String::from("hunter2 🔑"), key.chacha_key())
+            .unwrap();
+
+        assert_eq!(
+            token.decrypt(key.chacha_key()).unwrap().expose(),
+            "hunter2 🔑"
+        );
+        assert_eq!(
+            token.with_str(key.chacha_key(), |token| token.len()),
+            Ok("hunter2 🔑".len())
+        );
+    }
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let mut secret_bytes = ZeroizeBytes::new();
+        secret_bytes.set(chacha20poly1305::aead::bytes::BytesMut::from(
+            &b"swordfish"[..],
+        ));
+        let mut vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        vault.insert_key(*b"named-key-000001", ZeroizeArray::new([0x41u8; 32]));
+
+        assert_eq!(
+            format!("{:?}", ZeroizeArray::new([0x41u8; 32])),
+            "ZeroizeArray([REDACTED; 32])"
+        );
+        assert_eq!(
+            format!("{:?}", ZeroizeBytesArray::<32>::csprng()),
+            "ZeroizeBytesArray([REDACTED; 32])"
+        );
+        assert_eq!(format!("{secret_bytes:?}"), "ZeroizeBytes([REDACTED; 9])");
+        assert_eq!(
+            format!("{:?}", ZeroizeString::new(String::from("swordfish"))),
+            "ZeroizeString([REDACTED])"
+        );
+
+        let key = ZeroizeArray::<32>::csprng();
+        let store = EncryptedMem::<32>::builder()
+            .nonce([0xABu8; 24].into())
+            .build()
+            .unwrap();
+        let ciphertext = format!("{:?}", store.ciphertext().expose());
+        let debug = format!("{store:?}");
+        assert!(debug.starts_with("EncryptedMem { algorithm: 1, nonce: abababab"));
+        assert!(!debug.contains(&ciphertext[1..ciphertext.len() - 1]));
+        let store =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x41u8; 32]), key.chacha_key()).unwrap();
+        let ciphertext = format!("{:?}", store.ciphertext().expose());
+        let debug = format!("{store:?}");
+        assert!(debug.contains("ciphertext: [REDACTED; 48]"));
+        assert!(!debug.contains(&ciphertext[1..ciphertext.len() - 1]));
+
+        let vault = format!("{vault:?}");
+        assert!(vault.contains("SealingKey<32>([REDACTED])"));
+        assert!(!vault.contains("65, 65"));
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn encrypted_value_round_trips_pod_layouts() {
+        use zeroize::Zeroize;
+
+        #[repr(C)]
+        #[derive(Clone, Copy, PartialEq, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+        struct KeyMetadata {
+            id: u32,
+            flags: u32,
+            created_at: u64,
+        }
+
+        impl Zeroize for KeyMetadata {
+            fn zeroize(&mut self) {
+                self.id.zeroize();
+                self.flags.zeroize();
+                self.created_at.zeroize();
+            }
+        }
+
+        let key = ZeroizeArray::<32>::csprng();
+
+        let counters =
+            EncryptedValue::<[u64; 4]>::seal([1, 2, 3, u64::MAX], key.chacha_key()).unwrap();
+        assert_eq!(counters.open(key.chacha_key()), Ok([1, 2, 3, u64::MAX]));
+
+        let metadata = KeyMetadata {
+            id: 7,
+            flags: 0b101,
+            created_at: 1_672_531_200,
+        };
+        let sealed = EncryptedValue::<KeyMetadata>::seal(metadata, key.chacha_key()).unwrap();
+        assert_eq!(
+            sealed.encrypted_bytes().ciphertext().expose().len(),
+            core::mem::size_of::<KeyMetadata>() + TAG_LENGTH
+        );
+        assert_eq!(
+            sealed.open_with(key.chacha_key(), |opened| opened.created_at),
+            Ok(1_672_531_200)
+        );
+
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(sealed.open(wrong_key.chacha_key()).err(), Some(WRONG_KEY));
+    }
+
+    #[test]
+    fn randomness_is_pluggable_and_fallible() {
+        use rand_core::{CryptoRng, RngCore};
+
+        struct FailingRng;
+
+        impl RngCore for FailingRng {
+            fn next_u32(&mut self) -> u32 {
+                unreachable!()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                unreachable!()
+            }
+
+            fn fill_bytes(&mut self, _dest: &mut [u8]) {
+                unreachable!()
+            }
+
+            fn try_fill_bytes(&mut self, _dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                Err(core::num::NonZeroU32::new(rand_core::Error::CUSTOM_START)
+                    .unwrap()
+                    .into())
+            }
+        }
+
+        impl CryptoRng for FailingRng {}
+
+        assert_eq!(
+            ZeroizeArray::<32>::try_csprng_from(&mut FailingRng).err(),
+            Some(SecureMemError::RandomnessUnavailable)
+        );
+        assert!(matches!(
+            EncryptedMem::<32>::builder().rng(&mut FailingRng).build(),
+            Err(SecureMemError::RandomnessUnavailable)
+        ));
+
+        let key = ZeroizeArray::<32>::try_csprng_from(&mut rand_core::OsRng).unwrap();
+        assert!(key != ZeroizeArray::zeroed());
+        assert!(EncryptedMem::<32>::builder()
+            .rng(&mut rand_core::OsRng)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn rotate_nonce_refreshes_the_ciphertext() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x33u8; 32]), key.chacha_key()).unwrap();
+        let old_nonce = *store.nonce();
+        let old_ciphertext = store.ciphertext().expose().clone();
+
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            store.rotate_nonce(wrong_key.chacha_key()).err(),
+            Some(WRONG_KEY)
+        );
+        assert_eq!(store.nonce(), &old_nonce);
+        assert_eq!(store.ciphertext().expose(), &old_ciphertext);
+
+        store.rotate_nonce(key.chacha_key()).unwrap();
+
+        assert_ne!(store.nonce(), &old_nonce);
+        assert_ne!(store.ciphertext().expose(), &old_ciphertext);
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[0x33u8; 32]
+        );
+    }
+
+    #[test]
+    fn stream_chunks_round_trip_and_stay_in_order() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut encryptor = EncryptedStream::<XChaCha8Poly1305>::new(key.chacha_key()).unwrap();
+        let nonce_prefix = encryptor.nonce_prefix().to_vec();
+        assert_eq!(
+            nonce_prefix.len(),
+            EncryptedStream::<XChaCha8Poly1305>::NONCE_PREFIX_LENGTH
+        );
+
+        let first = encryptor.encrypt_chunk(&[1u8; 64]).unwrap();
+        let second = encryptor.encrypt_chunk(&[2u8; 64]).unwrap();
+        let last = encryptor.finalize(&[3u8; 10]).unwrap();
+
+        let decryptor = || {
+            EncryptedStream::<XChaCha8Poly1305>::from_nonce_prefix(key.chacha_key(), &nonce_prefix)
+                .unwrap()
+        };
+
+        let mut in_order = decryptor();
+        assert_eq!(
+            &in_order.decrypt_chunk(first.expose()).unwrap().expose()[..],
+            &[1u8; 64]
+        );
+        assert_eq!(
+            &in_order.decrypt_chunk(second.expose()).unwrap().expose()[..],
+            &[2u8; 64]
+        );
+        assert_eq!(
+            &in_order.finalize_decrypt(last.expose()).unwrap().expose()[..],
+            &[3u8; 10]
+        );
+
+        // Swapped chunks, a non-last chunk passed as last, and a truncated stream all fail
+        let mut reordered = decryptor();
+        assert_eq!(
+            reordered.decrypt_chunk(second.expose()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+        let mut truncated = decryptor();
+        truncated.decrypt_chunk(first.expose()).unwrap();
+        assert_eq!(
+            truncated.finalize_decrypt(second.expose()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+        let mut last_as_middle = decryptor();
+        last_as_middle.decrypt_chunk(first.expose()).unwrap();
+        last_as_middle.decrypt_chunk(second.expose()).unwrap();
+        assert_eq!(
+            last_as_middle.decrypt_chunk(last.expose()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+
+        assert_eq!(
+            EncryptedStream::<XChaCha8Poly1305>::from_nonce_prefix(key.chacha_key(), &[0u8; 3])
+                .err()
+                .map(|_| ()),
+            Some(())
+        );
+    }
+
+    #[test]
+    fn secure_string_validates_and_redacts() {
+        let password: SecureString = "correct horse".parse().unwrap();
+
+        assert_eq!(password.as_str(), "correct horse");
+        assert_eq!(password.len(), 13);
+        assert!(!password.is_empty());
+        assert!(password.expose_with(|password| password.starts_with("correct")));
+        assert_eq!(format!("{password:?}"), "SecureString([REDACTED])");
+
+        let mut invalid = ZeroizeBytes::new();
+        invalid.set(chacha20poly1305::aead::bytes::BytesMut::from(
+            &[0xFFu8, 0xFE][..],
+        ));
+        assert_eq!(
+            SecureString::from_utf8(invalid).err(),
+            Some(SecureMemError::InvalidString)
+        );
+        assert!("".parse::<SecureString>().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn base64_round_trips_and_rejects_malformed_text() {
+        let key = ZeroizeArray::<32>::csprng();
+        let store =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x61u8; 32]), key.chacha_key()).unwrap();
+
+        let encoded = store.to_base64();
+        let restored = EncryptedMem::<32>::from_base64(encoded.expose()).unwrap();
+        assert_eq!(
+            restored
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            &[0x61u8; 32]
+        );
+
+        assert!(!encoded.expose().contains(['=', '+', '/']));
+
+        let standard = store.to_base64_with(Base64Alphabet::Standard);
+        let restored =
+            EncryptedMem::<32>::from_base64_with(standard.expose(), Base64Alphabet::Standard)
+                .unwrap();
+        assert_eq!(restored.nonce(), store.nonce());
+        let unpadded = standard.expose().trim_end_matches('=');
+        assert_ne!(unpadded, standard.expose());
+        assert!(matches!(
+            EncryptedMem::<32>::from_base64_with(unpadded, Base64Alphabet::Standard),
+            Err(SecureMemError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            EncryptedMem::<32>::from_base64(standard.expose()),
+            Err(SecureMemError::InvalidEncoding)
+        ));
+        let spaced = format!("{} {}", &encoded.expose()[..8], &encoded.expose()[8..]);
+        assert!(matches!(
+            EncryptedMem::<32>::from_base64(&spaced),
+            Err(SecureMemError::InvalidEncoding)
+        ));
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_round_trips_and_rejects_malformed_text() {
+        let key = ZeroizeArray::<32>::from_hex(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191A1B1C1D1E1F",
+        )
+        .unwrap();
+        assert_eq!(key.get(0x1F), Some(0x1F));
+
+        let store =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x62u8; 32]), key.chacha_key()).unwrap();
+        let encoded = store.to_hex();
+        let restored = EncryptedMem::<32>::from_hex(encoded.expose()).unwrap();
+        assert_eq!(
+            restored
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .expose_borrowed(),
+            &[0x62u8; 32]
+        );
+
+        assert!(matches!(
+            EncryptedMem::<32>::from_hex(&encoded.expose()[1..]),
+            Err(SecureMemError::InvalidEncoding)
+        ));
+        assert_eq!(
+            ZeroizeArray::<2>::from_hex("abc").err(),
+            Some(SecureMemError::InvalidEncoding)
+        );
+        assert_eq!(
+            ZeroizeArray::<2>::from_hex("ab cd").err(),
+            Some(SecureMemError::InvalidEncoding)
+        );
+        assert_eq!(
+            ZeroizeArray::<2>::from_hex("ab").err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert_eq!(
+            ZeroizeArray::<2>::from_hex("abcdef").err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert_eq!(
+            key.to_hex().expose(),
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+        );
+
+        let bytes = ZeroizeBytes::from_hex("00ff7A").unwrap();
+        assert_eq!(&bytes.expose()[..], &[0x00, 0xFF, 0x7A]);
+        assert_eq!(bytes.to_hex().expose(), "00ff7a");
+        assert!(ZeroizeBytes::from_hex("").unwrap().expose().is_empty());
+        for malformed in ["0", "00f", "zz", "0x00"] {
+            assert!(matches!(
+                ZeroizeBytes::from_hex(malformed),
+                Err(SecureMemError::InvalidEncoding)
+            ));
+        }
+    }
+
+    #[test]
+    fn duplicate_reseals_under_a_fresh_nonce() {
+        let key = ZeroizeArray::<32>::csprng();
+        let original =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x44u8; 32]), key.chacha_key()).unwrap();
+
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            original.duplicate(wrong_key.chacha_key()).err(),
+            Some(WRONG_KEY)
+        );
+
+        let copy = original.duplicate(key.chacha_key()).unwrap();
+        assert_ne!(copy.nonce(), original.nonce());
+        assert_ne!(copy.ciphertext().expose(), original.ciphertext().expose());
+        for store in [&original, &copy] {
+            assert_eq!(
+                store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+                &[0x44u8; 32]
+            );
+        }
+    }
+
+    #[test]
+    fn clones_do_not_share_storage() {
+        use chacha20poly1305::aead::bytes::BytesMut;
+        use zeroize::Zeroize;
+
+        let mut bytes = ZeroizeBytes::new();
+        bytes.set(BytesMut::from(&b"shared?"[..]));
+        let copy = bytes.clone();
+        assert_ne!(bytes.expose().as_ptr(), copy.expose().as_ptr());
+
+        bytes.zeroize();
+        assert_eq!(&copy.expose()[..], b"shared?");
+    }
+
+    #[test]
+    fn defaults_match_the_empty_constructors() {
+        assert!(ZeroizeArray::<16>::default() == ZeroizeArray::zeroed());
+        assert!(ZeroizeBytes::default().is_empty());
+        assert_eq!(ZeroizeBytesArray::<16>::default().expose().capacity(), 16);
+        assert_eq!(
+            ZeroizeBytesArray::<16>::with_capacity(4)
+                .expose()
+                .capacity(),
+            16
+        );
+        assert_eq!(ZeroizeBytes::with_capacity(24).expose().capacity(), 24);
+
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::with_capacity(0);
+        assert_eq!(store.ciphertext().expose().capacity(), 32 + TAG_LENGTH);
+        store
+            .encrypt(&ZeroizeArray::new([7u8; 32]), key.chacha_key())
+            .unwrap();
+        assert_eq!(store.ciphertext().expose().capacity(), 32 + TAG_LENGTH);
+        assert!(EncryptedMem::<32>::default().nonce() != store.nonce());
+    }
+
+    #[test]
+    fn lengths_are_reported_without_exposing() {
+        use chacha20poly1305::aead::bytes::BytesMut;
+
+        let mut bytes = ZeroizeBytes::new();
+        assert!(bytes.is_empty());
+        bytes.set(BytesMut::from(&b"seven!!"[..]));
+        assert_eq!(bytes.len(), 7);
+        assert!(!bytes.is_empty());
+
+        let mut array = ZeroizeBytesArray::<4>::new();
+        assert_eq!((array.len(), array.is_empty()), (0, true));
+        array.set(BytesMut::from(&[1u8, 2, 3, 4][..]));
+        assert_eq!((array.len(), array.is_empty()), (4, false));
+
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        assert!(store.is_empty());
+        store
+            .encrypt(&ZeroizeArray::new([1u8; 32]), key.chacha_key())
+            .unwrap();
+        assert_eq!(store.len(), 32 + TAG_LENGTH);
+        assert_eq!(store.capacity(), 32 + TAG_LENGTH);
+        assert!(!store.is_empty());
+        assert_eq!(ZeroizeBytes::with_capacity(12).capacity(), 12);
+        assert_eq!(array.capacity(), 4);
+    }
+
+    #[test]
+    fn arrays_convert_from_slices_and_vectors() {
+        let key = ZeroizeArray::<4>::try_from(&[1u8, 2, 3, 4][..]).unwrap();
+        assert_eq!(key.expose_borrowed(), &[1, 2, 3, 4]);
+        assert_eq!(
+            ZeroizeArray::<4>::try_from(&[1u8, 2, 3][..]).err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        );
+
+        let key = ZeroizeArray::<4>::try_from(vec![5u8, 6, 7, 8]).unwrap();
+        assert_eq!(key.expose_borrowed(), &[5, 6, 7, 8]);
+        assert_eq!(
+            ZeroizeArray::<4>::try_from(vec![0u8; 5]).err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 4,
+                actual: 5
+            })
+        );
+
+        let bytes = ZeroizeBytes::from_vec(b"from a vector".to_vec());
+        assert_eq!(&bytes.expose()[..], b"from a vector");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn shared_vault_serves_concurrent_readers() {
+        use std::sync::Arc;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SealingKeyVault<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>>();
+
+        let vault = Arc::new(SharedVault::from(SealingKeyVault::<
+            32,
+            DEFAULT_VAULT_PAGES,
+            DEFAULT_VAULT_PAGE_SIZE,
+        >::new()));
+        let sealing_key = vault.sealing_key();
+        vault.insert_key(*b"shared-key-00001", ZeroizeArray::new([0x21u8; 32]));
+
+        let handles = (0..4)
+            .map(|_| {
+                let vault = Arc::clone(&vault);
+                std::thread::spawn(move || {
+                    (
+                        vault.sealing_key(),
+                        vault.get_key(b"shared-key-00001").unwrap(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let (key, named) = handle.join().unwrap();
+            assert!(key == sealing_key);
+            assert_eq!(named.expose_borrowed(), &[0x21u8; 32]);
+        }
+
+        assert!(vault.remove_key(b"shared-key-00001"));
+        assert!(vault.read(|vault| vault.get_key(b"shared-key-00001").is_none()));
+        vault.write(|vault| {
+            vault.insert_key(*b"shared-key-00002", ZeroizeArray::zeroed());
+        });
+        assert!(vault.get_key(b"shared-key-00002").is_some());
+    }
+
+    #[test]
+    fn verify_checks_integrity_without_returning_plaintext() {
+        let key = ZeroizeArray::<32>::csprng();
+        let store =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x31u8; 32]), key.chacha_key()).unwrap();
+        assert_eq!(store.verify(key.chacha_key()), Ok(()));
+
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(store.verify(wrong_key.chacha_key()), Err(WRONG_KEY));
+
+        let mut tampered = store.to_bytes().expose().to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        let tampered = EncryptedMem::<32>::from_bytes(&tampered).unwrap();
+        assert_eq!(tampered.verify(key.chacha_key()), Err(TAMPERED));
+        assert_eq!(
+            EncryptedMem::<32>::new().verify(key.chacha_key()),
+            Err(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn counter_nonces_are_sequential_and_never_wrap() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut counter = NonceCounter::<XChaCha8Poly1305>::new().unwrap();
+        let mut store = EncryptedMem::<32>::new();
+
+        for expected in 0..3 {
+            store
+                .encrypt_with_counter(
+                    &ZeroizeArray::new([0x33u8; 32]),
+                    key.chacha_key(),
+                    &mut counter,
+                )
+                .unwrap();
+            assert_eq!(
+                NonceCounter::<XChaCha8Poly1305>::counter_of(store.nonce()),
+                expected
+            );
+            assert_eq!(&store.nonce()[..12], counter.prefix());
+            assert_eq!(
+                store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+                &[0x33u8; 32]
+            );
+        }
+
+        let limit = NonceCounter::<XChaCha8Poly1305>::COUNTER_LIMIT;
+        let mut nearly_done =
+            NonceCounter::<XChaCha8Poly1305>::from_parts(counter.prefix(), limit - 1).unwrap();
+        let last = nearly_done.next_nonce().unwrap();
+        assert_eq!(&last[12..], &[0xFFu8; 12]);
+        assert_eq!(
+            nearly_done.next_nonce(),
+            Err(SecureMemError::CounterExhausted)
+        );
+        assert_eq!(
+            store
+                .encrypt_with_counter(&ZeroizeArray::zeroed(), key.chacha_key(), &mut nearly_done)
+                .err(),
+            Some(SecureMemError::CounterExhausted)
+        );
+        assert_eq!(
+            NonceCounter::<XChaCha8Poly1305>::from_parts(counter.prefix(), limit).err(),
+            Some(SecureMemError::CounterExhausted)
+        );
+        assert!(NonceCounter::<XChaCha8Poly1305>::from_parts(&[0u8; 4], 0).is_err());
+    }
+
+    #[test]
+    fn sealing_key_splits_into_threshold_shares() {
+        let vault = SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::new();
+        let shares = vault.split(3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for picked in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let subset = picked.map(|index| shares[index].clone());
+            let recovered =
+                SealingKeyVault::<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>::reconstruct(
+                    &subset,
+                )
+                .unwrap();
+            assert!(recovered == vault.sealing_key());
+        }
+
+        type Vault = SealingKeyVault<32, DEFAULT_VAULT_PAGES, DEFAULT_VAULT_PAGE_SIZE>;
+        assert_eq!(
+            Vault::reconstruct(&shares[..2]).err(),
+            Some(SecureMemError::InvalidShares)
+        );
+        let duplicated = [shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert_eq!(
+            Vault::reconstruct(&duplicated).err(),
+            Some(SecureMemError::InvalidShares)
+        );
+        assert_eq!(
+            Vault::reconstruct(&[]).err(),
+            Some(SecureMemError::InvalidShares)
+        );
+        assert_eq!(vault.split(0, 3).err(), Some(SecureMemError::InvalidShares));
+        assert_eq!(vault.split(4, 3).err(), Some(SecureMemError::InvalidShares));
+
+        let single = vault.split(1, 1).unwrap();
+        assert!(Vault::reconstruct(&single).unwrap() == vault.sealing_key());
+    }
+
+    #[test]
+    fn arrays_split_into_sized_halves() {
+        let mut material = [0u8; 64];
+        material[32..].fill(0xBB);
+        material[..32].fill(0xAA);
+
+        let (encryption_key, mac_key) = ZeroizeArray::new(material).split::<32, 32>();
+        assert_eq!(encryption_key.expose_borrowed(), &[0xAAu8; 32]);
+        assert_eq!(mac_key.expose_borrowed(), &[0xBBu8; 32]);
+
+        let (head, tail) = ZeroizeArray::new([7u8; 5]).try_split::<1, 4>().unwrap();
+        assert_eq!((head.expose(), tail.expose()), ([7u8], [7u8; 4]));
+        assert_eq!(
+            ZeroizeArray::new(material).try_split::<32, 16>().err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 64,
+                actual: 48
+            })
+        );
+
+        let (encryption_key, mac_key) = ZeroizeArray::new(material).split::<32, 32>();
+        let rejoined = encryption_key.concat::<32, 64>(mac_key);
+        assert_eq!(rejoined.expose(), material);
+        assert_eq!(
+            ZeroizeArray::new([1u8; 2])
+                .try_concat::<2, 5>(ZeroizeArray::new([2u8; 2]))
+                .err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 5,
+                actual: 4
+            })
+        );
+    }
+
+    #[cfg(feature = "aes-siv")]
+    #[test]
+    fn siv_is_deterministic_and_authenticated() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintext = ZeroizeArray::new([0x35u8; 32]);
+
+        let mut first = EncryptedMemSiv::<32>::new();
+        first.encrypt(&plaintext, key.chacha_key()).unwrap();
+        let mut second = EncryptedMemSiv::<32>::new();
+        second.encrypt(&plaintext, key.chacha_key()).unwrap();
+        assert!(first.ciphertext() == second.ciphertext());
+        assert_eq!(first.tag(), second.tag());
+        assert!(first.decrypt(key.chacha_key()).unwrap() == plaintext);
+
+        second
+            .encrypt_with_aad(&plaintext, key.chacha_key(), b"context")
+            .unwrap();
+        assert_ne!(first.tag(), second.tag());
+        assert_eq!(
+            second.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+        assert!(
+            second
+                .decrypt_with_aad(key.chacha_key(), b"context")
+                .unwrap()
+                == plaintext
+        );
+
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            first.decrypt(wrong_key.chacha_key()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+        assert_eq!(
+            EncryptedMemSiv::<32>::new().decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn fallible_constructors_draw_from_the_os_rng() {
+        let key = ZeroizeArray::<32>::try_csprng().unwrap();
+        assert!(key != ZeroizeArray::zeroed());
+        assert_eq!(ZeroizeBytesArray::<24>::try_csprng().unwrap().len(), 24);
+        assert_eq!(ZeroizeBytes::try_csprng::<40>().unwrap().len(), 40);
+
+        let first = EncryptedMem::<32>::try_new().unwrap();
+        let second = EncryptedMem::<32>::try_new().unwrap();
+        assert_ne!(first.nonce(), second.nonce());
+        assert!(first.is_empty());
+    }
+
+    #[test]
+    fn xor_mixes_and_unmixes_keys() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mask = ZeroizeArray::<32>::csprng();
+
+        let mut mixed = key.clone();
+        mixed.xor_in_place(&mask);
+        assert!(mixed != key);
+        mixed.xor_in_place(&mask);
+        assert!(mixed == key);
+
+        let unmasked = key.clone().xor(mask.clone()).xor(mask);
+        assert!(unmasked == key);
+
+        let mut bytes = ZeroizeBytes::from_vec(vec![0x0Fu8; 4]);
+        bytes.xor_in_place(&[0xF0u8; 4]).unwrap();
+        assert_eq!(&bytes.expose()[..], &[0xFFu8; 4]);
+        assert_eq!(
+            bytes.xor_in_place(&[0u8; 3]).err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(&bytes.expose()[..], &[0xFFu8; 4]);
+    }
+
+    #[test]
+    fn zero_checks_cover_every_byte() {
+        use chacha20poly1305::aead::bytes::BytesMut;
+
+        let mut first_set = [0u8; 32];
+        first_set[0] = 1;
+        let mut last_set = [0u8; 32];
+        last_set[31] = 0x80;
+
+        assert!(bool::from(ZeroizeArray::<32>::zeroed().ct_is_zero()));
+        assert!(!bool::from(ZeroizeArray::new(first_set).ct_is_zero()));
+        assert!(!bool::from(ZeroizeArray::new(last_set).ct_is_zero()));
+
+        for (bytes, is_zero) in [([0u8; 32], true), (first_set, false), (last_set, false)] {
+            let mut array = ZeroizeBytesArray::<32>::new();
+            array.set(BytesMut::from(&bytes[..]));
+            assert_eq!(bool::from(array.ct_is_zero()), is_zero);
+            assert_eq!(
+                bool::from(ZeroizeBytes::from_vec(bytes.to_vec()).ct_is_zero()),
+                is_zero
+            );
+        }
+        assert!(bool::from(ZeroizeBytes::new().ct_is_zero()));
+    }
+
+    #[test]
+    fn slices_compare_against_the_whole_secret() {
+        let secret = ZeroizeArray::new([0x5Au8; 16]);
+        let bytes = ZeroizeBytes::from_vec(vec![0x5Au8; 16]);
+
+        let mut late_difference = [0x5Au8; 16];
+        late_difference[15] ^= 1;
+
+        assert!(secret.ct_eq_slice(&[0x5Au8; 16]));
+        assert!(bytes.ct_eq_slice(&[0x5Au8; 16]));
+        assert!(!secret.ct_eq_slice(&late_difference));
+        assert!(!bytes.ct_eq_slice(&late_difference));
+        assert!(!secret.ct_eq_slice(&[0x5Au8; 15]));
+        assert!(!bytes.ct_eq_slice(&[0x5Au8; 17]));
+        assert!(!secret.ct_eq_slice(&[]));
+    }
+
+    #[test]
+    fn arrays_convert_to_and_from_plain_arrays() {
+        use zeroize::Zeroize;
+
+        fn wrap<T: Into<ZeroizeArray<8>>>(value: T) -> ZeroizeArray<8> {
+            value.into()
+        }
+
+        let array = wrap(*b"8 bytes!");
+        assert!(array.ct_eq_slice(b"8 bytes!"));
+
+        let mut inner = array.into_inner();
+        assert_eq!(&inner, b"8 bytes!");
+        inner.zeroize();
+        assert_eq!(inner, [0u8; 8]);
+
+        let round_trip = ZeroizeArray::from(ZeroizeArray::<4>::from([1, 2, 3, 4]).into_inner());
+        assert_eq!(round_trip.expose_borrowed(), &[1, 2, 3, 4]);
+        drop(round_trip);
+    }
+
+    #[test]
+    fn tokens_are_drawn_from_their_alphabet() {
+        let token = generate_token(40, TokenAlphabet::default()).unwrap();
+        assert_eq!(token.len(), 40);
+        assert!(token
+            .as_str()
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric()));
+        assert_eq!(format!("{token:?}"), "SecureString([REDACTED])");
+
+        let token = generate_token(64, TokenAlphabet::UrlSafe).unwrap();
+        assert!(token
+            .as_str()
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'));
+
+        let token = generate_token(33, TokenAlphabet::Hex).unwrap();
+        assert!(token
+            .as_str()
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')));
+
+        let token = generate_token(500, TokenAlphabet::Custom(b"ab")).unwrap();
+        assert_eq!(token.len(), 500);
+        assert!(token.as_str().contains('a') && token.as_str().contains('b'));
+
+        assert!(generate_token(0, TokenAlphabet::Hex).unwrap().is_empty());
+        for alphabet in [&b""[..], b"aba", "é".as_bytes()] {
+            assert_eq!(
+                generate_token(8, TokenAlphabet::Custom(alphabet)).err(),
+                Some(SecureMemError::InvalidAlphabet)
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn arrays_fill_from_slices_and_readers() {
+        let mut array = ZeroizeArray::<4>::zeroed();
+        array.try_fill_from_slice(&[1, 2, 3, 4]).unwrap();
+        assert_eq!(array.expose_borrowed(), &[1, 2, 3, 4]);
+        assert_eq!(
+            array.try_fill_from_slice(&[1, 2, 3]).err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(array.expose_borrowed(), &[1, 2, 3, 4]);
+
+        let mut reader = &b"key file contents"[..];
+        array.fill_from_reader(&mut reader).unwrap();
+        assert_eq!(array.expose_borrowed(), b"key ");
+        assert_eq!(reader, b"file contents");
+
+        let mut short = &b"ab"[..];
+        assert_eq!(
+            array.fill_from_reader(&mut short).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert!(bool::from(array.ct_is_zero()));
+
+        struct FailingReader;
+
+        impl std::io::Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::PermissionDenied.into())
+            }
+        }
+
+        assert_eq!(
+            array.fill_from_reader(&mut FailingReader).err(),
+            Some(SecureMemError::Io)
+        );
+
+        array.fill_from_slice([9u8; 4]);
+        assert_eq!(array.expose_borrowed(), &[9u8; 4]);
+    }
+
+    #[test]
+    fn sealer_reuses_one_cipher_for_many_secrets() {
+        let key = ZeroizeArray::<32>::csprng();
+        let sealer = Sealer::<XChaCha8Poly1305>::new(key.chacha_key());
+
+        let secrets = (0..8u8).map(|index| ZeroizeArray::new([index; 16]));
+        let sealed = secrets
+            .map(|secret| sealer.encrypt(&secret).unwrap())
+            .collect::<Vec<_>>();
+        assert!(sealed[0].nonce() != sealed[1].nonce());
+
+        sealed.iter().enumerate().for_each(|(index, sealed)| {
+            assert!(sealer
+                .decrypt(sealed)
+                .unwrap()
+                .ct_eq_slice(&[index as u8; 16]));
+            assert!(sealed
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .ct_eq_slice(&[index as u8; 16]));
+        });
+
+        let mut sealed_by_key = EncryptedMem::<16>::new();
+        sealed_by_key
+            .encrypt_with_aad(&ZeroizeArray::new([7u8; 16]), key.chacha_key(), b"aad")
+            .unwrap();
+        assert!(sealer
+            .decrypt_with_aad(&sealed_by_key, b"aad")
+            .unwrap()
+            .ct_eq_slice(&[7u8; 16]));
+        assert_eq!(sealer.decrypt(&sealed_by_key).err(), Some(TAMPERED));
+
+        let other = Sealer::<XChaCha8Poly1305>::new(ZeroizeArray::<32>::csprng().chacha_key());
+        assert_eq!(other.decrypt(&sealed[0]).err(), Some(WRONG_KEY));
+        assert_eq!(format!("{other:?}"), "Sealer([REDACTED])");
+    }
+
+    #[test]
+    fn secrets_swap_and_take_in_place() {
+        let mut first = ZeroizeArray::new([1u8; 8]);
+        let mut second = ZeroizeArray::new([2u8; 8]);
+        first.swap(&mut second);
+        assert!(first.ct_eq_slice(&[2u8; 8]));
+        assert!(second.ct_eq_slice(&[1u8; 8]));
+
+        let taken = first.take();
+        assert!(taken.ct_eq_slice(&[2u8; 8]));
+        assert!(bool::from(first.ct_is_zero()));
+
+        let mut short = ZeroizeBytes::from_vec(vec![3u8; 4]);
+        let mut long = ZeroizeBytes::from_vec(vec![4u8; 64]);
+        short.swap(&mut long);
+        assert!(short.ct_eq_slice(&[4u8; 64]));
+        assert!(long.ct_eq_slice(&[3u8; 4]));
+    }
+
+    #[test]
+    fn batches_seal_every_item_under_its_own_nonce() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintexts = (0..64u8)
+            .map(|index| ZeroizeArray::new([index; 32]))
+            .collect::<Vec<_>>();
+
+        let sealed = EncryptedMem::<32>::encrypt_batch(&plaintexts, key.chacha_key()).unwrap();
+        assert_eq!(sealed.len(), plaintexts.len());
+        sealed.iter().enumerate().for_each(|(index, sealed)| {
+            assert!(sealed
+                .decrypt(key.chacha_key())
+                .unwrap()
+                .ct_eq_slice(&[index as u8; 32]));
+            assert!(sealed.key_commitment().is_some() == cfg!(feature = "key-commitment"));
+        });
+
+        let nonces = sealed
+            .iter()
+            .map(|sealed| sealed.nonce().as_slice())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(nonces.len(), sealed.len());
+
+        assert!(EncryptedMem::<32>::encrypt_batch(&[], key.chacha_key())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn fingerprints_match_known_answers() {
+        // blake3::derive_key with the context "xor-securemem 2023-01-01 fingerprint v1"
+        let counting = core::array::from_fn::<u8, 32, _>(|index| index as u8);
+        let counting_fingerprint = ZeroizeArray::new(counting).fingerprint();
+        assert_eq!(
+            counting_fingerprint[..],
+            [
+                0xa3, 0x34, 0xb1, 0xa7, 0x2d, 0x1b, 0xe7, 0xc0, 0xe4, 0x30, 0x46, 0x8e, 0x7b, 0xc9,
+                0x60, 0xe1, 0x47, 0x3f, 0x27, 0x16, 0xef, 0xf2, 0xe4, 0x00, 0x32, 0x41, 0x5d, 0x9d,
+                0xf5, 0xff, 0x0b, 0xcb
+            ]
+        );
+        assert_eq!(
+            ZeroizeArray::<32>::zeroed().fingerprint_hex(),
+            "41fdaaebd190eb50"
+        );
+        assert_eq!(ZeroizeBytes::new().fingerprint_hex(), "6cc7a69e84ae0cbb");
+        assert_eq!(
+            ZeroizeBytes::from_vec(counting.to_vec()).fingerprint(),
+            counting_fingerprint
+        );
+
+        // The fingerprint is not a plain hash of the key
+        assert!(blake3::hash(&counting).as_bytes() != &counting_fingerprint);
+
+        let vault = SealingKeyVault::<32, 1, 64>::new();
+        assert_eq!(vault.fingerprint(), vault.sealing_key().fingerprint());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sealed_secrets_round_trip_through_files() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut sealed = EncryptedMem::<32>::new();
+        sealed
+            .encrypt(&ZeroizeArray::new([0x42u8; 32]), key.chacha_key())
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!(
+            "xor-securemem-{}-{}",
+            std::process::id(),
+            ZeroizeArray::<8>::csprng().fingerprint_hex()
+        ));
+        sealed.write_to_file(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let read = EncryptedMem::<32>::read_from_file(&path).unwrap();
+        assert!(read
+            .decrypt(key.chacha_key())
+            .unwrap()
+            .ct_eq_slice(&[0x42u8; 32]));
+
+        // Overwriting goes through a temporary file that is renamed over the old one and never left behind
+        sealed
+            .encrypt(&ZeroizeArray::new([0x43u8; 32]), key.chacha_key())
+            .unwrap();
+        sealed.write_to_file(&path).unwrap();
+        assert!(EncryptedMem::<32>::read_from_file(&path)
+            .unwrap()
+            .decrypt(key.chacha_key())
+            .unwrap()
+            .ct_eq_slice(&[0x43u8; 32]));
+        let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+        let leftovers = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter(|entry| {
+                let entry = entry.as_ref().unwrap().file_name();
+                let entry = entry.to_string_lossy();
+                entry.contains(&name) && *entry != *name
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        assert_eq!(
+            sealed
+                .write_to_file(path.join("missing").join("secret"))
+                .err(),
+            Some(SecureMemError::Io)
+        );
+        assert_eq!(
+            EncryptedMem::<16>::read_from_file(&path).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+
+        std::fs::write(&path, [b'x'; 128]).unwrap();
+        assert_eq!(
+            EncryptedMem::<32>::read_from_file(&path).err(),
+            Some(SecureMemError::InvalidHeader)
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            EncryptedMem::<32>::read_from_file(&path).err(),
+            Some(SecureMemError::Io)
+        );
+    }
+
+    #[cfg(feature = "hkdf")]
+    #[test]
+    fn hkdf_subkeys_match_rfc_5869() {
+        // Test case 1
+        let ikm = ZeroizeArray::new([0x0bu8; 22]);
+        let salt = (0x00..=0x0cu8).collect::<Vec<_>>();
+        let info = (0xf0..=0xf9u8).collect::<Vec<_>>();
+        assert!(ikm
+            .derive_subkey::<42>(Some(&salt), &info)
+            .ct_eq_slice(&hex(
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        )));
+
+        // Test case 2
+        let ikm = ZeroizeArray::new(core::array::from_fn::<u8, 80, _>(|index| index as u8));
+        let salt = (0x60..=0xafu8).collect::<Vec<_>>();
+        let info = (0xb0..=0xffu8).collect::<Vec<_>>();
+        assert!(ikm
+            .derive_subkey::<82>(Some(&salt), &info)
+            .ct_eq_slice(&hex(concat!(
+                "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c",
+                "59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71",
+                "cc30c58179ec3e87c14c01d5c1f3434f1d87"
+            ))));
+
+        // Test case 3, an empty salt is the same as no salt
+        let ikm = ZeroizeArray::new([0x0bu8; 22]);
+        let expected = hex(
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8",
+        );
+        assert!(ikm.derive_subkey::<42>(None, b"").ct_eq_slice(&expected));
+        assert!(ikm
+            .derive_subkey::<42>(Some(b""), b"")
+            .ct_eq_slice(&expected));
+        // The longest output runs the block counter up to its last value and extends the shorter ones
+        assert!(ikm.derive_subkey::<8160>(None, b"").expose_borrowed()[..42] == expected[..]);
+
+        let master = ZeroizeArray::<32>::csprng();
+        let database = master.derive_subkey::<32>(None, b"db-encryption");
+        assert!(database == master.derive_subkey(None, b"db-encryption"));
+        assert!(database != master.derive_subkey(None, b"session-mac"));
+        assert!(database != master.derive_subkey(Some(b"salt"), b"db-encryption"));
+        assert!(database != master);
+    }
+
+    #[test]
+    fn borrowed_xor_keeps_both_inputs() {
+        let key = ZeroizeArray::new([0b1010_1010u8; 16]);
+        let pad = ZeroizeArray::new([0b0110_0110u8; 16]);
+
+        let masked = &key ^ &pad;
+        assert!(masked.ct_eq_slice(&[0b1100_1100u8; 16]));
+        assert!(key.ct_eq_slice(&[0b1010_1010u8; 16]));
+        assert!(pad.ct_eq_slice(&[0b0110_0110u8; 16]));
+
+        assert!(&masked ^ &pad == key);
+        assert!(bool::from((&key ^ &key).ct_is_zero()));
+    }
+
+    #[test]
+    fn stack_containers_seal_without_the_heap() {
+        let key = ZeroizeArray::<32>::csprng();
+        let plaintext = ZeroizeArray::new([0x24u8; 48]);
+
+        let mut sealed = EncryptedMemStack::<48>::new();
+        assert_eq!(
+            sealed.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::AuthenticationFailed)
+        );
+
+        sealed
+            .encrypt_with_aad(&plaintext, key.chacha_key(), b"aad")
+            .unwrap();
+        assert!(sealed.ciphertext() != &plaintext);
+        assert!(sealed.decrypt_with_aad(key.chacha_key(), b"aad").unwrap() == plaintext);
+        assert_eq!(sealed.decrypt(key.chacha_key()).err(), Some(TAMPERED));
+        assert_eq!(
+            sealed
+                .decrypt_with_aad(ZeroizeArray::<32>::csprng().chacha_key(), b"aad")
+                .err(),
+            Some(WRONG_KEY)
+        );
+
+        let nonce = *sealed.nonce();
+        sealed.encrypt(&plaintext, key.chacha_key()).unwrap();
+        assert!(sealed.nonce() != &nonce);
+        let mut out = ZeroizeArray::<48>::csprng();
+        sealed.decrypt_into(key.chacha_key(), &mut out).unwrap();
+        assert!(out == plaintext);
+        assert_eq!(
+            sealed.key_commitment().is_some(),
+            cfg!(feature = "key-commitment")
+        );
+
+        // Everything is inline: the ciphertext, a 16 byte tag and a 24 byte nonce
+        assert!(core::mem::size_of::<EncryptedMemStack<48>>() >= 48 + 16 + 24);
+        assert_eq!(format!("{sealed:?}"), "EncryptedMemStack([REDACTED; 48])");
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        fn check<const N: usize>(key: [u8; N], message: &[u8], expected: &str) {
+            let key = ZeroizeArray::new(key);
+            assert!(key.hmac_sha256(message).ct_eq_slice(&hex(expected)));
+            assert!(key.verify_hmac(message, &hex(expected)));
+
+            // The same tag when the message arrives in pieces, including pieces that straddle a block boundary
+            [1, 7, 63, 64, 65].iter().for_each(|&size| {
+                let mut streamed = HmacSha256::new(&key);
+                message.chunks(size).for_each(|chunk| {
+                    streamed.update(chunk);
+                });
+                assert!(streamed.verify(&hex(expected)));
+            });
+        }
+
+        let counting = core::array::from_fn::<u8, 25, _>(|index| index as u8 + 1);
+        check(
+            [0x0b; 20],
+            b"Hi There",
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+        );
+        check(
+            *b"Jefe",
+            b"what do ya want for nothing?",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        );
+        check(
+            [0xaa; 20],
+            &[0xdd; 50],
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+        );
+        check(
+            counting,
+            &[0xcd; 50],
+            "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+        );
+        check(
+            [0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+        );
+        check(
+            [0xaa; 131],
+            b"This is a test using a larger than block-size key and a larger than block-size data. \
+              The key needs to be hashed before being used by the HMAC algorithm.",
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+        );
+
+        // Test case 5 compares a truncated tag, which `verify_hmac` deliberately rejects
+        let key = ZeroizeArray::new([0x0c; 20]);
+        let tag = key.hmac_sha256(b"Test With Truncation");
+        assert_eq!(
+            &tag.expose_borrowed()[..16],
+            &hex("a3b6167473100ee06e0c796c2955552b")[..]
+        );
+        assert!(!key.verify_hmac(b"Test With Truncation", &tag.expose_borrowed()[..16]));
+        assert!(!key.verify_hmac(b"Test With Truncation!", tag.expose_borrowed()));
+        assert_eq!(
+            format!("{:?}", HmacSha256::new(&key)),
+            "HmacSha256([REDACTED])"
+        );
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex_strings_decode_by_value() {
+        let key = ZeroizeArray::<4>::from_hex_string(String::from("DEADbeef")).unwrap();
+        assert!(key.ct_eq_slice(&[0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(
+            ZeroizeArray::<4>::from_hex_string(String::from("deadbee")).err(),
+            Some(SecureMemError::InvalidEncoding)
+        );
+        assert_eq!(
+            ZeroizeArray::<4>::from_hex_string(String::from("deadbeeg")).err(),
+            Some(SecureMemError::InvalidEncoding)
+        );
+        // Too short and too long are the same mistake and give the same error
+        assert_eq!(
+            ZeroizeArray::<4>::from_hex("dead").err(),
+            Some(SecureMemError::InvalidLength)
+        );
+        assert_eq!(
+            ZeroizeArray::<4>::from_hex("deadbeef00").err(),
+            Some(SecureMemError::InvalidLength)
+        );
+
+        let bytes = ZeroizeBytes::from_hex_string(String::from("00ff10")).unwrap();
+        assert_eq!(&bytes.expose()[..], &[0x00, 0xff, 0x10]);
+        assert_eq!(
+            ZeroizeBytes::from_hex_string(String::from("00f")).err(),
+            Some(SecureMemError::InvalidEncoding)
+        );
+        assert_eq!(
+            ZeroizeBytes::from_hex_string(String::from("0x00")).err(),
+            Some(SecureMemError::InvalidEncoding)
+        );
+    }
+
+    #[cfg(feature = "x25519")]
+    #[test]
+    fn x25519_peers_open_each_others_blobs() {
+        let alice = EphemeralDh::generate();
+        let bob = EphemeralDh::generate();
+        let alice_key = alice.agree(&bob.public_key()).unwrap();
+        let bob_key = bob.agree(&alice.public_key()).unwrap();
+        assert!(alice_key == bob_key);
+
+        let from_alice =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0x5au8; 32]), alice_key.chacha_key())
+                .unwrap();
+        let from_bob =
+            EncryptedMem::<32>::seal(ZeroizeArray::new([0xa5u8; 32]), bob_key.chacha_key())
+                .unwrap();
+        assert_eq!(
+            from_alice.decrypt(bob_key.chacha_key()).unwrap().expose(),
+            [0x5au8; 32]
+        );
+        assert_eq!(
+            from_bob.decrypt(alice_key.chacha_key()).unwrap().expose(),
+            [0xa5u8; 32]
+        );
+
+        let eve = EphemeralDh::try_generate().unwrap();
+        assert!(eve.agree(&alice.public_key()).unwrap() != alice_key);
+        assert!(!format!("{alice:?}").contains(&format!("{:?}", alice_key.expose())));
+    }
+
+    #[test]
+    fn detached_tags_travel_separately() {
+        let key = ZeroizeArray::<32>::csprng();
+        let (sealed, tag) =
+            DetachedCiphertext::<32>::encrypt(&ZeroizeArray::new([0x3cu8; 32]), key.chacha_key())
+                .unwrap();
+
+        assert!(sealed.ciphertext().expose() != [0x3cu8; 32]);
+        assert_eq!(
+            sealed.decrypt(key.chacha_key(), &tag).unwrap().expose(),
+            [0x3cu8; 32]
+        );
+
+        let mut tampered = tag;
+        tampered[0] ^= 1;
+        assert_eq!(
+            sealed.decrypt(key.chacha_key(), &tampered).err(),
+            Some(TAMPERED)
+        );
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        assert_eq!(
+            sealed.decrypt(wrong_key.chacha_key(), &tag).err(),
+            Some(WRONG_KEY)
+        );
+
+        // The parts reassemble after being stored apart, and the aad is bound to the tag
+        let (sealed, tag) = DetachedCiphertext::<32>::encrypt_with_aad(
+            &ZeroizeArray::new([0x3cu8; 32]),
+            key.chacha_key(),
+            b"header",
+        )
+        .unwrap();
+        let reassembled = DetachedCiphertext::<32>::from_parts(
+            sealed.ciphertext().clone(),
+            *sealed.nonce(),
+            sealed.key_commitment().copied(),
+        );
+        assert_eq!(
+            reassembled
+                .decrypt_with_aad(key.chacha_key(), &tag, b"header")
+                .unwrap()
+                .expose(),
+            [0x3cu8; 32]
+        );
+        assert!(reassembled.decrypt(key.chacha_key(), &tag).is_err());
+        assert_eq!(
+            format!("{reassembled:?}"),
+            "DetachedCiphertext([REDACTED; 32])"
+        );
+    }
+
+    #[test]
+    fn shamir_thresholds_reconstruct_in_any_order() {
+        type Vault = SealingKeyVault<32, 1, DEFAULT_VAULT_PAGE_SIZE>;
+        let vault = Vault::new();
+
+        (2..=5u8).for_each(|threshold| {
+            let mut shares = vault.split(threshold, threshold + 2).unwrap();
+
+            // Every rotation of the reversed shares, so each one leads at least once
+            shares.reverse();
+            (0..shares.len()).for_each(|_| {
+                shares.rotate_left(1);
+                let picked = &shares[..threshold as usize];
+                assert!(Vault::reconstruct(picked).unwrap() == vault.sealing_key());
+                assert_eq!(
+                    Vault::reconstruct(&picked[1..]).err(),
+                    Some(SecureMemError::InvalidShares)
+                );
+            });
+
+            // Rewriting the recorded threshold lets fewer shares interpolate, but not to the sealing key
+            let forged = shares[..threshold as usize - 1]
+                .iter()
+                .map(|share| {
+                    let mut bytes = share.expose().clone();
+                    bytes[1] = threshold - 1;
+                    let mut forged = ZeroizeBytesArray::<SHAMIR_SHARE_LENGTH>::new();
+                    forged.set(bytes);
+                    forged
+                })
+                .collect::<Vec<_>>();
+            assert!(Vault::reconstruct(&forged).unwrap() != vault.sealing_key());
+        });
+    }
+
+    #[cfg(feature = "aes-gcm")]
+    #[test]
+    fn aes_gcm_counters_start_from_random_prefixes() {
+        assert_eq!(NonceCounter::<Aes256Gcm>::PREFIX_LENGTH, 4);
+        assert_eq!(NonceCounter::<Aes256Gcm>::COUNTER_LIMIT, 1 << 64);
+
+        let mut first = NonceCounter::<Aes256Gcm>::new().unwrap();
+        let mut second = NonceCounter::<Aes256Gcm>::new().unwrap();
+        assert_ne!(first.prefix(), second.prefix());
+        assert_ne!(first.next_nonce().unwrap(), second.next_nonce().unwrap());
+
+        let nonce = first.next_nonce().unwrap();
+        assert_eq!(NonceCounter::<Aes256Gcm>::counter_of(&nonce), 1);
+        assert_eq!(&nonce[..4], first.prefix());
+    }
+
+    #[test]
+    fn truncated_shares_are_invalid() {
+        type Vault = SealingKeyVault<32, 1, DEFAULT_VAULT_PAGE_SIZE>;
+        let shares = Vault::new().split(2, 3).unwrap();
+
+        let mut truncated = shares[1].clone();
+        truncated.truncate(1);
+        assert_eq!(
+            Vault::reconstruct(&[shares[0].clone(), truncated]).err(),
+            Some(SecureMemError::InvalidShares)
+        );
+        assert_eq!(
+            Vault::reconstruct(&[shares[0].clone(), ZeroizeBytesArray::new()]).err(),
+            Some(SecureMemError::InvalidShares)
+        );
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn hmac_pads_every_final_block_length() {
+        // After the 64 byte inner key block, 56 to 63 byte messages leave no room for the length in the final block
+        let key = ZeroizeArray::new(core::array::from_fn::<u8, 32, _>(|index| index as u8 + 1));
+        [
+            (
+                55,
+                "dd4e627582858fc1ce3ba15caef075a88f7802dbe00dbd5bdaf21d78d31395b6",
+            ),
+            (
+                56,
+                "6e12785300767756a24a3df2a4abcb8769c05947bae7e7439ea29343fbc6ea16",
+            ),
+            (
+                60,
+                "db6ea98fe4ab02d82e395bd9e50fd6145fec42bdd596cffa54593512288dffa0",
+            ),
+            (
+                63,
+                "a0195ff47a0f35d8d74598fc1cf35e42a86e12b8bf18edb96971114fb9f35d19",
+            ),
+            (
+                64,
+                "799c85098c10c297812f933b05d9aa2c13970467509cdd0eedd95daaecdd8abf",
+            ),
+            (
+                119,
+                "37c6e82c782141d50d7d39e5057bd79c6a24578511714be8b8aa782aad40ef7a",
+            ),
+            (
+                120,
+                "0a26bfeaf537447461afa8e5821b102f561a9e730e2d01b2f1e2b1391ca20eeb",
+            ),
+            (
+                127,
+                "c80c5ef6276c382c31ad344cfe82d17db0ea6193dc26e7171f28ce2686544519",
+            ),
+            (
+                128,
+                "6ad6cc4ff536231f6fc4b60149a3557c752a0dcd2e98627c30372e1eb769f4ad",
+            ),
+        ]
+        .iter()
+        .for_each(|&(length, expected)| {
+            let message = (0..length)
+                .map(|index| (index * 7 % 251) as u8)
+                .collect::<Vec<u8>>();
+            let expected = hex(expected);
+
+            assert!(key.verify_hmac(&message, &expected), "{length}");
+            let mut streamed = HmacSha256::new(&key);
+            message.chunks(33).for_each(|chunk| {
+                streamed.update(chunk);
+            });
+            assert!(streamed.verify(&expected), "{length}");
+        });
+    }
+
+    #[cfg(feature = "mnemonic")]
+    #[test]
+    fn mnemonics_match_the_bip39_vectors() {
+        fn check<const N: usize>(entropy: &str, phrase: &str) {
+            let entropy = ZeroizeArray::<N>::try_from(hex(entropy)).unwrap();
+            assert_eq!(entropy.to_mnemonic().expose(), phrase);
+            assert!(ZeroizeArray::<N>::from_mnemonic(phrase).unwrap() == entropy);
+        }
+
+        // From the BIP39 reference implementation's vectors.json
+        check::<16>(
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        );
+        check::<16>(
+            "80808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+        );
+        check::<16>(
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+        );
+        check::<24>(
+            "6610b25967cdcca9d59875f5cb50b0ea75433311869e930b",
+            "gravity machine north sort system female filter attitude volume fold club stay feature office \
+             ecology stable narrow fog",
+        );
+        check::<32>(
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+        );
+        check::<32>(
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+        );
+        check::<32>(
+            "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c",
+            "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy \
+             gospel tennis maple dilemma loan word shrug inflict delay length",
+        );
+        check::<32>(
+            "9f6a2878b2520799a44ef18bc7df394e7061a224d2c33cd015b157d746869863",
+            "panda eyebrow bullet gorilla call smoke muffin taste mesh discover soft ostrich alcohol speed \
+             nation flash devote level hobby quick inner drive ghost inside",
+        );
+
+        // A typo and a swapped word fail in different ways
+        let phrase = "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong";
+        assert!(ZeroizeArray::<16>::from_mnemonic(phrase).is_ok());
+        assert_eq!(
+            ZeroizeArray::<16>::from_mnemonic("zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrongg")
+                .err(),
+            Some(SecureMemError::UnknownWord)
+        );
+        assert_eq!(
+            ZeroizeArray::<16>::from_mnemonic("zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong zoo")
+                .err(),
+            Some(SecureMemError::InvalidChecksum)
+        );
+        assert_eq!(
+            ZeroizeArray::<32>::from_mnemonic(phrase).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+
+        let generated = ZeroizeArray::<32>::csprng();
+        let phrase = generated.to_mnemonic();
+        assert_eq!(phrase.expose().split(' ').count(), 24);
+        assert!(ZeroizeArray::<32>::from_mnemonic(phrase.expose()).unwrap() == generated);
+    }
 }
@@ -0,0 +1,140 @@
+use crate::ZeroizeArray;
+use chacha20poly1305::aead::generic_array::GenericArray;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+const BLOCK_LENGTH: usize = 64;
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5C;
+
+impl<const N: usize> ZeroizeArray<N> {
+    // HMAC-SHA256 (RFC 2104) keyed by `self`, the key is only ever copied into the wiped MAC state
+    pub fn hmac_sha256(&self, message: &[u8]) -> ZeroizeArray<32> {
+        let mut mac = HmacSha256::new(self);
+        mac.update(message);
+
+        mac.finalize()
+    }
+
+    // Constant time in the tag, a tag of the wrong length is rejected
+    pub fn verify_hmac(&self, message: &[u8], tag: &[u8]) -> bool {
+        self.hmac_sha256(message).ct_eq_slice(tag)
+    }
+}
+
+// A streaming HMAC-SHA256 for messages that arrive in pieces. The `hmac` crate cannot wipe its state, so this one
+// wipes both hash states and the padded outer key when it drops.
+pub struct HmacSha256 {
+    inner: Sha256,
+    outer_key: [u8; BLOCK_LENGTH],
+}
+
+impl HmacSha256 {
+    pub fn new<const N: usize>(key: &ZeroizeArray<N>) -> Self {
+        key.expose_with(Self::with_key)
+    }
+
+    // For keys that are not secret on their own, such as an HKDF salt
+    pub(crate) fn with_key(key: &[u8]) -> Self {
+        // Keys longer than a block are hashed first, shorter ones are zero padded
+        let mut padded = [0u8; BLOCK_LENGTH];
+        match key.len() > BLOCK_LENGTH {
+            true => {
+                let mut hash = Sha256::new();
+                hash.update(key);
+                hash.finalize_into(&mut padded[..32]);
+            }
+            false => padded[..key.len()].copy_from_slice(key),
+        }
+
+        let mut inner = Sha256::new();
+        let mut outer_key = padded;
+        padded.iter_mut().for_each(|byte| *byte ^= IPAD);
+        outer_key.iter_mut().for_each(|byte| *byte ^= OPAD);
+        inner.update(&padded);
+        padded.zeroize();
+
+        HmacSha256 { inner, outer_key }
+    }
+
+    pub fn update(&mut self, message: &[u8]) -> &mut Self {
+        self.inner.update(message);
+
+        self
+    }
+
+    pub fn finalize(mut self) -> ZeroizeArray<32> {
+        let mut tag = ZeroizeArray::<32>::zeroed();
+        tag.expose_with_mut(|tag| {
+            self.inner.finalize_into(tag);
+
+            let mut outer = Sha256::new();
+            outer.update(&self.outer_key);
+            outer.update(tag);
+            outer.finalize_into(tag);
+        });
+
+        tag
+    }
+
+    pub fn verify(self, tag: &[u8]) -> bool {
+        self.finalize().ct_eq_slice(tag)
+    }
+}
+
+impl Zeroize for HmacSha256 {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+        self.outer_key.zeroize();
+    }
+}
+
+impl Drop for HmacSha256 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for HmacSha256 {}
+
+impl core::fmt::Debug for HmacSha256 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("HmacSha256([REDACTED])")
+    }
+}
+
+// `sha2::Sha256` cannot wipe itself, so this wrapper overwrites the whole hasher, buffered block included, with a
+// fresh one through a volatile write the compiler cannot drop
+pub(crate) struct Sha256(sha2::Sha256);
+
+impl Sha256 {
+    pub(crate) fn new() -> Self {
+        Sha256(sha2::Sha256::default())
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        sha2::Digest::update(&mut self.0, data);
+    }
+
+    // Writes the first `out.len()` bytes of the digest and wipes the state
+    pub(crate) fn finalize_into(&mut self, out: &mut [u8]) {
+        let mut digest = GenericArray::default();
+        sha2::Digest::finalize_into_reset(&mut self.0, &mut digest);
+        out.copy_from_slice(&digest[..out.len()]);
+        digest.as_mut_slice().zeroize();
+        self.zeroize();
+    }
+}
+
+impl Zeroize for Sha256 {
+    fn zeroize(&mut self) {
+        // `sha2::Sha256` has no `Drop` impl, so overwriting it without dropping the old value leaks nothing
+        unsafe { core::ptr::write_volatile(&mut self.0, sha2::Sha256::default()) };
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl Drop for Sha256 {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{page::page_size, SecureMemError};
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, PoisonError},
+};
+
+// Only heap backed storage can be locked: `ZeroizeArray` is moved by value so its address is not stable. Drop paths
+// wipe the bytes before unlocking so the secret can never be paged out between the two steps.
+//
+// `mlock` is not reference counted by the OS, so unlocking one buffer would unlock every other secret sharing its
+// pages. The registry counts the locked regions per page and only unlocks a page once its last region is released.
+struct LockRegistry {
+    regions: BTreeMap<usize, usize>,
+    pages: BTreeMap<usize, usize>,
+}
+
+static REGISTRY: Mutex<LockRegistry> = Mutex::new(LockRegistry {
+    regions: BTreeMap::new(),
+    pages: BTreeMap::new(),
+});
+
+pub(crate) fn lock(ptr: *const u8, len: usize) -> Result<(), SecureMemError> {
+    if len == 0 {
+        return Ok(());
+    }
+
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    if registry.regions.contains_key(&(ptr as usize)) {
+        return Ok(());
+    }
+
+    let page_size = page_size();
+    let (start, end) = page_bounds(ptr as usize, len, page_size);
+
+    if !sys::lock(start, end - start) {
+        return Err(SecureMemError::MemoryLock);
+    }
+
+    (start..end).step_by(page_size).for_each(|page| {
+        *registry.pages.entry(page).or_insert(0) += 1;
+    });
+    registry.regions.insert(ptr as usize, len);
+
+    Ok(())
+}
+
+// A no-op for regions that were never locked, so drop paths can call it unconditionally
+pub(crate) fn unlock(ptr: *const u8) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    let len = match registry.regions.remove(&(ptr as usize)) {
+        Some(len) => len,
+        None => return,
+    };
+
+    let page_size = page_size();
+    let (start, end) = page_bounds(ptr as usize, len, page_size);
+
+    (start..end).step_by(page_size).for_each(|page| {
+        if let Some(count) = registry.pages.get_mut(&page) {
+            *count -= 1;
+
+            if *count == 0 {
+                registry.pages.remove(&page);
+                sys::unlock(page, page_size);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+pub(crate) fn is_locked(ptr: *const u8) -> bool {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .regions
+        .contains_key(&(ptr as usize))
+}
+
+fn page_bounds(address: usize, len: usize, page_size: usize) -> (usize, usize) {
+    let start = address - address % page_size;
+    let end = (address + len).div_ceil(page_size) * page_size;
+
+    (start, end)
+}
+
+#[cfg(unix)]
+mod sys {
+    pub(super) fn lock(address: usize, len: usize) -> bool {
+        unsafe { libc::mlock(address as *const libc::c_void, len) == 0 }
+    }
+
+    pub(super) fn unlock(address: usize, len: usize) {
+        unsafe {
+            libc::munlock(address as *const libc::c_void, len);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use windows_sys::Win32::System::Memory::{VirtualLock, VirtualUnlock};
+
+    pub(super) fn lock(address: usize, len: usize) -> bool {
+        unsafe { VirtualLock(address as *const core::ffi::c_void, len) != 0 }
+    }
+
+    pub(super) fn unlock(address: usize, len: usize) {
+        unsafe {
+            VirtualUnlock(address as *const core::ffi::c_void, len);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    pub(super) fn lock(_address: usize, _len: usize) -> bool {
+        false
+    }
+
+    pub(super) fn unlock(_address: usize, _len: usize) {}
+}
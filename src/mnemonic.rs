@@ -0,0 +1,221 @@
+use crate::{mac::Sha256, SecureMemError, ZeroizeArray, ZeroizeString};
+use alloc::string::String;
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+use zeroize::Zeroize;
+
+// The official BIP39 English wordlist, its SHA-256 is checked against the published digest in `mnemonic_tests`
+const WORDLIST: &str = include_str!("bip39_english.txt");
+const WORD_COUNT: usize = 2048;
+const MAX_WORD_LENGTH: usize = 8;
+const BITS_PER_WORD: usize = 11;
+// Entropy and checksum bits, with room to read three bytes at the last word
+const BUFFER_LENGTH: usize = 35;
+
+const _: () = assert!(
+    line_count(WORDLIST.as_bytes()) == WORD_COUNT,
+    "the BIP39 wordlist has 2048 words"
+);
+
+impl<const N: usize> ZeroizeArray<N> {
+    // BIP39 (English) with `self` as the entropy, 16 bytes give 12 words and 32 bytes give 24. Each word is picked
+    // by scanning the whole wordlist, so which words the secret maps to does not show in the timing.
+    pub fn to_mnemonic(&self) -> ZeroizeString {
+        const {
+            assert!(
+                N.is_multiple_of(4) && N >= 16 && N <= 32,
+                "BIP39 entropy is 16 to 32 bytes"
+            )
+        };
+
+        let mut bits = [0u8; BUFFER_LENGTH];
+        bits[..N].copy_from_slice(self.expose_borrowed());
+        bits[N] = checksum(self.expose_borrowed());
+
+        // Reserved up front so the phrase is never reallocated and copied while it grows
+        let mut phrase = String::with_capacity(Self::mnemonic_words() * (MAX_WORD_LENGTH + 1));
+        let mut word = [0u8; MAX_WORD_LENGTH];
+        (0..Self::mnemonic_words()).for_each(|position| {
+            let length = select_word(read_index(&bits, position), &mut word);
+            if position > 0 {
+                phrase.push(' ');
+            }
+            word[..length]
+                .iter()
+                .for_each(|&letter| phrase.push(char::from(letter)));
+        });
+        word.zeroize();
+        bits.zeroize();
+
+        ZeroizeString::new(phrase)
+    }
+
+    // The reverse of `to_mnemonic`. A word outside the wordlist is `UnknownWord` and a phrase whose last bits do
+    // not match its checksum is `InvalidChecksum`, the caller still owns `phrase` and is responsible for wiping it.
+    pub fn from_mnemonic(phrase: &str) -> Result<Self, SecureMemError> {
+        const {
+            assert!(
+                N.is_multiple_of(4) && N >= 16 && N <= 32,
+                "BIP39 entropy is 16 to 32 bytes"
+            )
+        };
+
+        if phrase.split_whitespace().count() != Self::mnemonic_words() {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let mut bits = [0u8; BUFFER_LENGTH];
+        let outcome = phrase
+            .split_whitespace()
+            .enumerate()
+            .try_for_each(|(position, word)| {
+                let index = find_word(word).ok_or(SecureMemError::UnknownWord)?;
+                write_index(&mut bits, position, index);
+
+                Ok(())
+            });
+        if let Err(error) = outcome {
+            bits.zeroize();
+
+            return Err(error);
+        }
+
+        let mut entropy = ZeroizeArray::<N>::zeroed();
+        entropy.expose_with_mut(|entropy| entropy.copy_from_slice(&bits[..N]));
+        let matches: bool = checksum(entropy.expose_borrowed()).ct_eq(&bits[N]).into();
+        bits.zeroize();
+
+        match matches {
+            true => Ok(entropy),
+            false => Err(SecureMemError::InvalidChecksum),
+        }
+    }
+
+    const fn mnemonic_words() -> usize {
+        (N * 8 + N / 4) / BITS_PER_WORD
+    }
+}
+
+// The first `N / 4` bits of the SHA-256 of the entropy, left aligned in one byte
+fn checksum(entropy: &[u8]) -> u8 {
+    let mut digest = [0u8; 1];
+    let mut hash = Sha256::new();
+    hash.update(entropy);
+    hash.finalize_into(&mut digest);
+
+    let checksum = digest[0] & (0xff00u16 >> (entropy.len() / 4)) as u8;
+    digest.zeroize();
+
+    checksum
+}
+
+fn read_index(bits: &[u8; BUFFER_LENGTH], position: usize) -> u16 {
+    let start = position * BITS_PER_WORD;
+    let window = u32::from_be_bytes([0, bits[start / 8], bits[start / 8 + 1], bits[start / 8 + 2]]);
+
+    ((window >> (24 - BITS_PER_WORD - start % 8)) & 0x7ff) as u16
+}
+
+fn write_index(bits: &mut [u8; BUFFER_LENGTH], position: usize, index: u16) {
+    (0..BITS_PER_WORD).for_each(|bit| {
+        let at = position * BITS_PER_WORD + bit;
+        let value = ((index >> (BITS_PER_WORD - 1 - bit)) & 1) as u8;
+        bits[at / 8] |= value << (7 - at % 8);
+    });
+}
+
+// Copies the word at `index` into `word` and returns its length, touching every word on the way
+fn select_word(index: u16, word: &mut [u8; MAX_WORD_LENGTH]) -> usize {
+    let mut length = 0u8;
+    WORDLIST.lines().enumerate().for_each(|(candidate, line)| {
+        let chosen = (candidate as u16).ct_eq(&index);
+        let padded = pad(line.as_bytes());
+        word.iter_mut()
+            .zip(padded)
+            .for_each(|(letter, candidate)| letter.conditional_assign(&candidate, chosen));
+        length.conditional_assign(&(line.len() as u8), chosen);
+    });
+
+    usize::from(length)
+}
+
+// Compares `word` against every entry in constant time, only its length decides how long this takes
+fn find_word(word: &str) -> Option<u16> {
+    if word.len() > MAX_WORD_LENGTH {
+        return None;
+    }
+
+    let mut padded = pad(word.as_bytes());
+    let mut index = 0u16;
+    let mut found = subtle::Choice::from(0);
+    WORDLIST.lines().enumerate().for_each(|(candidate, line)| {
+        let matches = pad(line.as_bytes()).ct_eq(&padded) & line.len().ct_eq(&word.len());
+        index.conditional_assign(&(candidate as u16), matches);
+        found |= matches;
+    });
+    padded.zeroize();
+
+    match bool::from(found) {
+        true => Some(index),
+        false => None,
+    }
+}
+
+fn pad(word: &[u8]) -> [u8; MAX_WORD_LENGTH] {
+    let mut padded = [0u8; MAX_WORD_LENGTH];
+    padded[..word.len()].copy_from_slice(word);
+
+    padded
+}
+
+const fn line_count(bytes: &[u8]) -> usize {
+    let mut lines = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'\n' {
+            lines += 1;
+        }
+        index += 1;
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod mnemonic_tests {
+    use super::*;
+
+    #[test]
+    fn wordlist_matches_the_published_digest() {
+        // SHA-256 of english.txt as published in the BIP39 repository
+        let published = "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda";
+        let mut digest = [0u8; 32];
+        let mut hash = Sha256::new();
+        hash.update(WORDLIST.as_bytes());
+        hash.finalize_into(&mut digest);
+
+        let encoded = digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        assert_eq!(encoded, published);
+        assert!(WORDLIST
+            .lines()
+            .all(|word| (3..=MAX_WORD_LENGTH).contains(&word.len())));
+    }
+
+    #[test]
+    fn indices_round_trip_through_the_bit_buffer() {
+        let mut bits = [0u8; BUFFER_LENGTH];
+        (0..24).for_each(|position| write_index(&mut bits, position, (position * 89) as u16));
+        (0..24)
+            .for_each(|position| assert_eq!(read_index(&bits, position), (position * 89) as u16));
+
+        let mut word = [0u8; MAX_WORD_LENGTH];
+        assert_eq!(select_word(2047, &mut word), 3);
+        assert_eq!(&word[..3], b"zoo");
+        assert_eq!(find_word("zoo"), Some(2047));
+        assert_eq!(find_word("abandon"), Some(0));
+        assert_eq!(find_word("aban"), None);
+        assert_eq!(find_word("zoo\0"), None);
+    }
+}
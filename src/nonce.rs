@@ -0,0 +1,132 @@
+use crate::{Cipher, SecureMemError, XChaCha8Poly1305};
+use chacha20poly1305::aead::{generic_array::typenum::Unsigned, AeadCore, Nonce};
+
+// The big endian message counter at the end of every nonce is at most 96 bits, and always leaves at least this much
+// of the nonce to the random prefix
+const MAX_COUNTER_LENGTH: usize = 12;
+const MIN_PREFIX_LENGTH: usize = 4;
+
+// Hands out sequential nonces: a random prefix followed by a counter that is incremented for every nonce. The
+// XChaCha ciphers get a 96 bit prefix and counter, AES-256-GCM's 96 bit nonce is split into a 32 bit prefix and a
+// 64 bit counter, so separate counters under one AES-GCM key should stay well below 2^16 to keep their prefixes
+// apart. The counter never wraps, once every value has been used `next_nonce` fails with `CounterExhausted` for good.
+pub struct NonceCounter<C: Cipher = XChaCha8Poly1305> {
+    nonce: Nonce<C>,
+    counter: u128,
+}
+
+impl<C: Cipher> NonceCounter<C> {
+    pub const COUNTER_LENGTH: usize = {
+        let nonce_length = <C as AeadCore>::NonceSize::USIZE;
+        assert!(
+            nonce_length >= MIN_PREFIX_LENGTH * 2,
+            "the nonce is too short for a random prefix and a counter"
+        );
+
+        match nonce_length - MIN_PREFIX_LENGTH < MAX_COUNTER_LENGTH {
+            true => nonce_length - MIN_PREFIX_LENGTH,
+            false => MAX_COUNTER_LENGTH,
+        }
+    };
+    pub const PREFIX_LENGTH: usize = <C as AeadCore>::NonceSize::USIZE - Self::COUNTER_LENGTH;
+    // One past the last counter value that still fits in the nonce
+    pub const COUNTER_LIMIT: u128 = 1 << (Self::COUNTER_LENGTH * 8);
+
+    #[cfg(feature = "getrandom")]
+    pub fn new() -> Result<Self, SecureMemError> {
+        let mut nonce = Nonce::<C>::default();
+        crate::store::try_fill_random(&mut rand_core::OsRng, &mut nonce[..Self::PREFIX_LENGTH])?;
+
+        Ok(NonceCounter { nonce, counter: 0 })
+    }
+
+    // Resumes a sequence from persisted state, `counter` is the next value to hand out
+    pub fn from_parts(prefix: &[u8], counter: u128) -> Result<Self, SecureMemError> {
+        if prefix.len() != Self::PREFIX_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+        if counter >= Self::COUNTER_LIMIT {
+            return Err(SecureMemError::CounterExhausted);
+        }
+
+        let mut nonce = Nonce::<C>::default();
+        nonce[..Self::PREFIX_LENGTH].copy_from_slice(prefix);
+
+        Ok(NonceCounter { nonce, counter })
+    }
+
+    pub fn prefix(&self) -> &[u8] {
+        &self.nonce[..Self::PREFIX_LENGTH]
+    }
+
+    pub fn counter(&self) -> u128 {
+        self.counter
+    }
+
+    pub fn next_nonce(&mut self) -> Result<Nonce<C>, SecureMemError> {
+        if self.counter >= Self::COUNTER_LIMIT {
+            return Err(SecureMemError::CounterExhausted);
+        }
+
+        let mut nonce = self.nonce.clone();
+        nonce[Self::PREFIX_LENGTH..]
+            .copy_from_slice(&self.counter.to_be_bytes()[16 - Self::COUNTER_LENGTH..]);
+        self.counter += 1;
+
+        Ok(nonce)
+    }
+
+    // The counter a nonce from this sequence carries, for receivers checking for replays or reordering
+    pub fn counter_of(nonce: &Nonce<C>) -> u128 {
+        let mut counter = [0u8; 16];
+        counter[16 - Self::COUNTER_LENGTH..].copy_from_slice(&nonce[Self::PREFIX_LENGTH..]);
+
+        u128::from_be_bytes(counter)
+    }
+}
+
+impl<C: Cipher> core::fmt::Debug for NonceCounter<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("NonceCounter")
+            .field("counter", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod nonce_tests {
+    use super::*;
+    use crate::{EncryptedMem, ZeroizeArray};
+
+    #[test]
+    fn counters_seal_without_the_os_rng() {
+        // What a target without `getrandom` does: the prefix and the key come from its own entropy source
+        let key = ZeroizeArray::new([0x07u8; 32]);
+        let mut counter = NonceCounter::<XChaCha8Poly1305>::from_parts(&[0x5a; 12], 0).unwrap();
+        let mut sealed = EncryptedMem::<32>::builder()
+            .nonce(counter.next_nonce().unwrap())
+            .build()
+            .unwrap();
+        sealed
+            .encrypt_with_counter(
+                &ZeroizeArray::new([0x3c; 32]),
+                key.chacha_key(),
+                &mut counter,
+            )
+            .unwrap();
+
+        assert_eq!(
+            sealed.decrypt(key.chacha_key()).unwrap().expose(),
+            [0x3c; 32]
+        );
+        assert_eq!(
+            NonceCounter::<XChaCha8Poly1305>::counter_of(sealed.nonce()),
+            1
+        );
+        #[cfg(not(feature = "getrandom"))]
+        assert_eq!(
+            EncryptedMem::<32>::builder().build().err(),
+            Some(SecureMemError::RandomnessUnavailable)
+        );
+    }
+}
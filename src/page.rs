@@ -0,0 +1,119 @@
+use core::{
+    alloc::Layout,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+// Vault pages get a dedicated allocation starting on a page boundary and padded to a whole number of pages, so
+// page granular calls like `mlock` and `madvise` cover the vault and never any unrelated heap memory
+pub(crate) struct VaultPages<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> {
+    ptr: NonNull<[[u8; VAULT_PAGE_SIZE]; VAULT_PAGES]>,
+    layout: Option<Layout>,
+}
+
+impl<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize>
+    VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    pub(crate) const SIZE: usize = VAULT_PAGES * VAULT_PAGE_SIZE;
+
+    pub(crate) fn zeroed() -> Self {
+        if Self::SIZE == 0 {
+            return VaultPages {
+                ptr: NonNull::dangling(),
+                layout: None,
+            };
+        }
+
+        let page_size = page_size();
+        let layout = Layout::from_size_align(Self::SIZE.next_multiple_of(page_size), page_size)
+            .expect("vault size overflows the address space");
+
+        // An all zero byte pattern is a valid value for a byte array
+        let ptr = unsafe { alloc::alloc::alloc_zeroed(layout) }
+            .cast::<[[u8; VAULT_PAGE_SIZE]; VAULT_PAGES]>();
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::alloc::handle_alloc_error(layout));
+
+        VaultPages {
+            ptr,
+            layout: Some(layout),
+        }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr().cast()
+    }
+}
+
+impl<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Deref
+    for VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    type Target = [[u8; VAULT_PAGE_SIZE]; VAULT_PAGES];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> DerefMut
+    for VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+// Wiping is the owner's job, this only hands the allocation back
+impl<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Drop
+    for VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+    fn drop(&mut self) {
+        if let Some(layout) = self.layout {
+            unsafe { alloc::alloc::dealloc(self.as_ptr().cast_mut(), layout) }
+        }
+    }
+}
+
+// The allocation is uniquely owned just like a `Box`
+unsafe impl<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Send
+    for VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+}
+
+unsafe impl<const VAULT_PAGES: usize, const VAULT_PAGE_SIZE: usize> Sync
+    for VaultPages<VAULT_PAGES, VAULT_PAGE_SIZE>
+{
+}
+
+#[cfg(all(unix, any(feature = "mlock", feature = "dontdump")))]
+pub(crate) fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+// Every Windows architecture uses 4 KiB pages, and `VirtualLock` rounds ranges out to page boundaries itself
+#[cfg(not(all(unix, any(feature = "mlock", feature = "dontdump"))))]
+pub(crate) fn page_size() -> usize {
+    4096
+}
+
+#[cfg(feature = "dontdump")]
+pub(crate) fn exclude_from_core_dumps(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_DONTDUMP);
+    }
+
+    #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+    unsafe {
+        libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_NOCORE);
+    }
+
+    // Other platforms have no way to exclude memory from core dumps
+    let _ = ptr;
+}
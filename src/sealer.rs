@@ -0,0 +1,122 @@
+use crate::{Cipher, EncryptedMem, SecureMemError, XChaCha8Poly1305, ZeroizeArray};
+use chacha20poly1305::Key;
+#[cfg(feature = "getrandom")]
+use {
+    crate::{store::try_nonce_from_rng, ZeroizeBytesArray},
+    alloc::{collections::BTreeSet, vec::Vec},
+    chacha20poly1305::aead::Nonce,
+    rand_core::OsRng,
+};
+
+// Seals and opens many `EncryptedMem`s under one key with the cipher initialized once. The saving depends on the
+// backend: AES-256-GCM expands its key schedule and GHASH key in `KeyInit`, so reusing it skips that per call, the
+// XChaCha ciphers only copy the key there and derive their subkey per nonce, so they gain little. On one x86_64
+// machine with AES-NI a round trip of a 32 byte secret took about 62% of the per call time with AES-256-GCM and
+// 85-90% with XChaCha, the ratio depends on the hardware, so measure it with
+// `cargo run --release --example sealer_throughput --all-features`. Both the cipher and the key copy kept for key
+// commitments are wiped when the `Sealer` drops.
+pub struct Sealer<C: Cipher = XChaCha8Poly1305> {
+    cipher: C,
+    key: ZeroizeArray<32>,
+}
+
+impl<C: Cipher> Sealer<C> {
+    pub fn new(key: &Key) -> Self {
+        let mut copy = ZeroizeArray::zeroed();
+        copy.expose_with_mut(|copy| copy.copy_from_slice(key));
+
+        Sealer {
+            cipher: C::new(key),
+            key: copy,
+        }
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt<const N: usize>(
+        &self,
+        plaintext: &ZeroizeArray<N>,
+    ) -> Result<EncryptedMem<N, C>, SecureMemError> {
+        self.encrypt_with_aad(plaintext, b"")
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_aad<const N: usize>(
+        &self,
+        plaintext: &ZeroizeArray<N>,
+        aad: &[u8],
+    ) -> Result<EncryptedMem<N, C>, SecureMemError> {
+        self.seal_under_nonce(plaintext, aad, try_nonce_from_rng(&mut OsRng)?)
+    }
+
+    // Every item gets its own nonce from the operating system RNG like `encrypt`, and a nonce already used earlier in
+    // the batch is drawn again, so no two items of one batch ever share a nonce
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_batch<const N: usize>(
+        &self,
+        plaintexts: &[ZeroizeArray<N>],
+    ) -> Result<Vec<EncryptedMem<N, C>>, SecureMemError> {
+        let mut used = BTreeSet::new();
+
+        plaintexts
+            .iter()
+            .map(|plaintext| {
+                let nonce = loop {
+                    let nonce: Nonce<C> = try_nonce_from_rng(&mut OsRng)?;
+                    if used.insert(nonce.clone()) {
+                        break nonce;
+                    }
+                };
+
+                self.seal_under_nonce(plaintext, b"", nonce)
+            })
+            .collect()
+    }
+
+    pub fn decrypt<const N: usize>(
+        &self,
+        sealed: &EncryptedMem<N, C>,
+    ) -> Result<ZeroizeArray<N>, SecureMemError> {
+        self.decrypt_with_aad(sealed, b"")
+    }
+
+    pub fn decrypt_with_aad<const N: usize>(
+        &self,
+        sealed: &EncryptedMem<N, C>,
+        aad: &[u8],
+    ) -> Result<ZeroizeArray<N>, SecureMemError> {
+        let mut plaintext = ZeroizeArray::<N>::zeroed();
+        sealed.open_with_cipher(&self.cipher, self.key.chacha_key(), aad, &mut plaintext)?;
+
+        Ok(plaintext)
+    }
+
+    #[cfg(feature = "getrandom")]
+    fn seal_under_nonce<const N: usize>(
+        &self,
+        plaintext: &ZeroizeArray<N>,
+        aad: &[u8],
+        nonce: Nonce<C>,
+    ) -> Result<EncryptedMem<N, C>, SecureMemError> {
+        let mut sealed = EncryptedMem::from_parts(ZeroizeBytesArray::new(), Nonce::<C>::default());
+        sealed.encrypt_with_cipher(plaintext, &self.cipher, self.key.chacha_key(), aad, nonce)?;
+
+        Ok(sealed)
+    }
+}
+
+impl<const N: usize, C: Cipher> EncryptedMem<N, C> {
+    // Initializes the cipher once for the whole batch, see `Sealer::encrypt_batch` for the nonce guarantee
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_batch(
+        plaintexts: &[ZeroizeArray<N>],
+        key: &Key,
+    ) -> Result<Vec<Self>, SecureMemError> {
+        Sealer::new(key).encrypt_batch(plaintexts)
+    }
+}
+
+impl<C: Cipher> core::fmt::Debug for Sealer<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Sealer([REDACTED])")
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{Cipher, EncryptedMem, ZeroizeBytesArray, KEY_COMMITMENT_LENGTH};
+use alloc::vec::Vec;
+use chacha20poly1305::aead::{bytes::BytesMut, Nonce};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+// Only the nonce and the ciphertext are ever serialized, there is no plaintext or key material in `EncryptedMem`
+#[derive(Serialize)]
+struct EncryptedMemRef<'a> {
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+    // Always written, skipping it would desync non self-describing formats such as bincode
+    key_commitment: Option<&'a [u8]>,
+}
+
+#[derive(Deserialize)]
+struct EncryptedMemOwned {
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+    #[serde(default)]
+    key_commitment: Option<Vec<u8>>,
+}
+
+impl<const N: usize, C: Cipher> Serialize for EncryptedMem<N, C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EncryptedMemRef {
+            nonce: self.nonce().as_slice(),
+            ciphertext: &self.ciphertext().expose()[..],
+            key_commitment: self.key_commitment().map(|commitment| &commitment[..]),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize, C: Cipher> Deserialize<'de> for EncryptedMem<N, C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let owned = EncryptedMemOwned::deserialize(deserializer)?;
+
+        if owned.nonce.len() != Self::NONCE_LENGTH {
+            return Err(D::Error::invalid_length(
+                owned.nonce.len(),
+                &"a nonce of the cipher's nonce length",
+            ));
+        }
+
+        // An `EncryptedMem` that was never encrypted into has an empty ciphertext
+        if !owned.ciphertext.is_empty() && owned.ciphertext.len() != N + Self::TAG_LENGTH {
+            return Err(D::Error::invalid_length(
+                owned.ciphertext.len(),
+                &"a ciphertext of N bytes followed by the cipher's tag",
+            ));
+        }
+
+        let mut ciphertext = ZeroizeBytesArray::<N>::with_capacity(N + Self::TAG_LENGTH);
+        ciphertext.set(BytesMut::from(&owned.ciphertext[..]));
+
+        let nonce = Nonce::<C>::clone_from_slice(&owned.nonce);
+
+        match owned.key_commitment {
+            Some(key_commitment) => {
+                let key_commitment: [u8; KEY_COMMITMENT_LENGTH] =
+                    key_commitment.as_slice().try_into().map_err(|_| {
+                        D::Error::invalid_length(key_commitment.len(), &"a 32 byte key commitment")
+                    })?;
+
+                Ok(EncryptedMem::from_parts_with_key_commitment(
+                    ciphertext,
+                    nonce,
+                    key_commitment,
+                ))
+            }
+            None => Ok(EncryptedMem::from_parts(ciphertext, nonce)),
+        }
+    }
+}
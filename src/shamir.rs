@@ -0,0 +1,156 @@
+use crate::{SecureMemError, ZeroizeArray, ZeroizeBytesArray};
+use zeroize::Zeroize;
+#[cfg(feature = "getrandom")]
+use {
+    crate::{store::try_fill_random, ZeroizeBytes},
+    alloc::vec::Vec,
+    chacha20poly1305::aead::bytes::BytesMut,
+    rand_core::OsRng,
+};
+
+const SECRET_LENGTH: usize = 32;
+
+// A share is its x coordinate, the threshold it was split with and one y coordinate per secret byte
+pub const SHAMIR_SHARE_LENGTH: usize = 2 + SECRET_LENGTH;
+
+// Shamir's secret sharing over GF(2^8) with the AES polynomial. Every secret byte gets its own random polynomial of
+// degree `threshold - 1` whose constant term is that byte, and share `x` holds each polynomial evaluated at `x`.
+#[cfg(feature = "getrandom")]
+pub(crate) fn split(
+    secret: &ZeroizeArray<SECRET_LENGTH>,
+    threshold: u8,
+    shares: u8,
+) -> Result<Vec<ZeroizeBytesArray<SHAMIR_SHARE_LENGTH>>, SecureMemError> {
+    if threshold == 0 || threshold > shares {
+        return Err(SecureMemError::InvalidShares);
+    }
+
+    // Coefficients of x^1 through x^(threshold - 1) for every secret byte, wiped when they drop
+    let length = SECRET_LENGTH * (threshold as usize - 1);
+    let mut coefficients = ZeroizeBytes::with_capacity(length);
+    coefficients.resize(length, 0);
+    try_fill_random(&mut OsRng, &mut coefficients.0[..])?;
+
+    let secret = secret.expose_borrowed();
+    let split = (1..=shares)
+        .map(|x| {
+            let mut share = BytesMut::zeroed(SHAMIR_SHARE_LENGTH);
+            share[0] = x;
+            share[1] = threshold;
+            share[2..].iter_mut().enumerate().for_each(|(index, y)| {
+                // Horner's rule from the highest degree coefficient down to the secret byte
+                let value = coefficients
+                    .expose()
+                    .chunks_exact(SECRET_LENGTH)
+                    .rev()
+                    .fold(0u8, |value, row| gf_mul(value, x) ^ row[index]);
+                *y = gf_mul(value, x) ^ secret[index];
+            });
+
+            let mut array = ZeroizeBytesArray::new();
+            array.set(share);
+            array
+        })
+        .collect();
+
+    Ok(split)
+}
+
+// Lagrange interpolation at zero over the first `threshold` shares
+pub(crate) fn reconstruct(
+    shares: &[ZeroizeBytesArray<SHAMIR_SHARE_LENGTH>],
+) -> Result<ZeroizeArray<SECRET_LENGTH>, SecureMemError> {
+    let threshold = match shares.first() {
+        Some(share) if share.len() == SHAMIR_SHARE_LENGTH => share.expose()[1],
+        _ => return Err(SecureMemError::InvalidShares),
+    };
+    if threshold == 0 || shares.len() < threshold as usize {
+        return Err(SecureMemError::InvalidShares);
+    }
+
+    // Every length is checked before any share is indexed
+    let shares = &shares[..threshold as usize];
+    if shares
+        .iter()
+        .any(|share| share.len() != SHAMIR_SHARE_LENGTH)
+    {
+        return Err(SecureMemError::InvalidShares);
+    }
+    for (position, share) in shares.iter().enumerate() {
+        let share = share.expose();
+        let duplicate = shares[..position]
+            .iter()
+            .any(|earlier| earlier.expose()[0] == share[0]);
+        if share[0] == 0 || share[1] != threshold || duplicate {
+            return Err(SecureMemError::InvalidShares);
+        }
+    }
+
+    let mut secret = ZeroizeArray::<SECRET_LENGTH>::zeroed();
+    secret.expose_with_mut(|secret| {
+        shares.iter().for_each(|share| {
+            let share = share.expose();
+            let x = share[0];
+            // The basis polynomial for `x` evaluated at zero, subtraction is xor in GF(2^8)
+            let basis = shares
+                .iter()
+                .map(|other| other.expose()[0])
+                .filter(|other| *other != x)
+                .fold(1u8, |basis, other| {
+                    gf_mul(basis, gf_mul(other, gf_inverse(other ^ x)))
+                });
+
+            let mut term = [0u8; SECRET_LENGTH];
+            term.iter_mut()
+                .zip(&share[2..])
+                .for_each(|(term, y)| *term = gf_mul(*y, basis));
+            secret
+                .iter_mut()
+                .zip(&term)
+                .for_each(|(secret, term)| *secret ^= term);
+            term.zeroize();
+        })
+    });
+
+    Ok(secret)
+}
+
+// Branch free carry-less multiplication reduced by x^8 + x^4 + x^3 + x + 1, so share bytes do not affect timing
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1B & carry);
+        b >>= 1;
+    }
+
+    product
+}
+
+// a^254 is the inverse of every non-zero element, computed with a fixed sequence of multiplications
+fn gf_inverse(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut power = a;
+    for bit in 0..8 {
+        if (254u8 >> bit) & 1 == 1 {
+            result = gf_mul(result, power);
+        }
+        power = gf_mul(power, power);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod shamir_tests {
+    use super::{gf_inverse, gf_mul};
+
+    #[test]
+    fn field_arithmetic_matches_the_aes_field() {
+        assert_eq!(gf_mul(0x57, 0x83), 0xC1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xFE);
+        assert_eq!(gf_mul(0, 0xFF), 0);
+        (1..=255u8).for_each(|a| assert_eq!(gf_mul(a, gf_inverse(a)), 1));
+    }
+}
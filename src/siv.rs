@@ -0,0 +1,135 @@
+use crate::{store::wipe_bytes_mut, SecureMemError, ZeroizeArray, ZeroizeBytesArray};
+use aes_siv::{aead::KeyInit, siv::Aes256Siv, Tag};
+use chacha20poly1305::{aead::bytes::BytesMut, Key};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+pub const SIV_TAG_LENGTH: usize = 16;
+
+const SIV_KEY_CONTEXT: &str = "xor-securemem 2023-01-01 EncryptedMemSiv key v1";
+
+// Deterministic, nonce misuse resistant sealing with AES-256-SIV (RFC 5297). There is no nonce to manage: the
+// synthetic IV is a MAC over the AAD and the plaintext, so sealing the same plaintext and AAD twice gives the same
+// ciphertext, which reveals equality but never the plaintext or the key.
+pub struct EncryptedMemSiv<const N: usize> {
+    ciphertext: ZeroizeBytesArray<N>,
+    tag: [u8; SIV_TAG_LENGTH],
+}
+
+impl<const N: usize> EncryptedMemSiv<N> {
+    pub fn new() -> Self {
+        EncryptedMemSiv {
+            ciphertext: ZeroizeBytesArray::new(),
+            tag: [0u8; SIV_TAG_LENGTH],
+        }
+    }
+
+    pub fn ciphertext(&self) -> &ZeroizeBytesArray<N> {
+        &self.ciphertext
+    }
+
+    pub fn tag(&self) -> &[u8; SIV_TAG_LENGTH] {
+        &self.tag
+    }
+
+    pub fn encrypt(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+    ) -> Result<&mut Self, SecureMemError> {
+        self.encrypt_with_aad(plaintext, key, b"")
+    }
+
+    pub fn encrypt_with_aad(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<&mut Self, SecureMemError> {
+        let mut buffer = BytesMut::with_capacity(N);
+        buffer.extend_from_slice(plaintext.expose_borrowed());
+        let tag = match siv(key).encrypt_in_place_detached([aad], &mut buffer[..]) {
+            Ok(tag) => tag,
+            Err(_) => {
+                wipe_bytes_mut(&mut buffer);
+
+                return Err(SecureMemError::Encryption);
+            }
+        };
+
+        self.ciphertext.zeroize();
+        self.ciphertext.set(buffer);
+        self.tag.copy_from_slice(&tag);
+
+        Ok(self)
+    }
+
+    pub fn decrypt(&self, key: &Key) -> Result<ZeroizeArray<N>, SecureMemError> {
+        self.decrypt_with_aad(key, b"")
+    }
+
+    // SIV decrypts before it can check the synthetic IV, so the candidate plaintext is wiped again when it fails
+    pub fn decrypt_with_aad(
+        &self,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<ZeroizeArray<N>, SecureMemError> {
+        if self.ciphertext.len() != N {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let mut out = ZeroizeArray::<N>::zeroed();
+        let outcome = out.expose_with_mut(|out| {
+            out.copy_from_slice(&self.ciphertext.expose()[..]);
+            siv(key).decrypt_in_place_detached([aad], out, Tag::from_slice(&self.tag))
+        });
+
+        match outcome {
+            Ok(()) => Ok(out),
+            Err(_) => {
+                out.zeroize();
+
+                Err(SecureMemError::AuthenticationFailed)
+            }
+        }
+    }
+}
+
+// SIV takes two AES-256 keys, both are derived from the one 32 byte key every other sealing type uses
+fn siv(key: &Key) -> Aes256Siv {
+    let mut siv_key = ZeroizeArray::<64>::zeroed();
+    siv_key.expose_with_mut(|siv_key| {
+        blake3::Hasher::new_derive_key(SIV_KEY_CONTEXT)
+            .update(key)
+            .finalize_xof()
+            .fill(siv_key)
+    });
+
+    Aes256Siv::new(siv_key.expose_borrowed().into())
+}
+
+impl<const N: usize> Default for EncryptedMemSiv<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Zeroize for EncryptedMemSiv<N> {
+    fn zeroize(&mut self) {
+        self.ciphertext.zeroize();
+        self.tag.zeroize();
+    }
+}
+
+impl<const N: usize> Drop for EncryptedMemSiv<N> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<const N: usize> ZeroizeOnDrop for EncryptedMemSiv<N> {}
+
+impl<const N: usize> core::fmt::Debug for EncryptedMemSiv<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "EncryptedMemSiv([REDACTED; {}])", self.ciphertext.len())
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    store::compute_key_commitment, Cipher, SecureMemError, XChaCha8Poly1305, ZeroizeArray,
+    KEY_COMMITMENT_LENGTH,
+};
+use chacha20poly1305::{
+    aead::{Nonce, Tag},
+    Key,
+};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// `EncryptedMem` without the heap: the ciphertext lives inline in a `ZeroizeArray<N>` and the tag next to it, since
+// `[u8; N + TAG_LENGTH]` cannot be spelled with const generics yet. Sealing and opening never allocate, and the whole
+// value is wiped in place when it drops, so moving it leaves the same stale copies as moving a `ZeroizeArray`.
+pub struct EncryptedMemStack<const N: usize, C: Cipher = XChaCha8Poly1305> {
+    ciphertext: ZeroizeArray<N>,
+    tag: Tag<C>,
+    nonce: Nonce<C>,
+    key_commitment: Option<[u8; KEY_COMMITMENT_LENGTH]>,
+}
+
+impl<const N: usize, C: Cipher> EncryptedMemStack<N, C> {
+    // Holds nothing until the first `encrypt`, so unlike `EncryptedMem::new` no nonce is drawn here
+    pub fn new() -> Self {
+        EncryptedMemStack {
+            ciphertext: ZeroizeArray::zeroed(),
+            tag: Tag::<C>::default(),
+            nonce: Nonce::<C>::default(),
+            key_commitment: None,
+        }
+    }
+
+    pub fn ciphertext(&self) -> &ZeroizeArray<N> {
+        &self.ciphertext
+    }
+
+    pub fn tag(&self) -> &Tag<C> {
+        &self.tag
+    }
+
+    pub fn nonce(&self) -> &Nonce<C> {
+        &self.nonce
+    }
+
+    pub fn key_commitment(&self) -> Option<&[u8; KEY_COMMITMENT_LENGTH]> {
+        self.key_commitment.as_ref()
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+    ) -> Result<&mut Self, SecureMemError> {
+        self.encrypt_with_aad(plaintext, key, b"")
+    }
+
+    // A fresh nonce per call like `EncryptedMem::encrypt_with_aad`, the plaintext is copied into the ciphertext
+    // array and sealed there in place
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_aad(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<&mut Self, SecureMemError> {
+        let (ciphertext, tag, nonce, key_commitment) = seal_inline::<N, C>(plaintext, key, aad)?;
+
+        self.zeroize();
+        self.ciphertext = ciphertext;
+        self.tag = tag;
+        self.nonce = nonce;
+        self.key_commitment = key_commitment;
+
+        Ok(self)
+    }
+
+    pub fn decrypt(&self, key: &Key) -> Result<ZeroizeArray<N>, SecureMemError> {
+        self.decrypt_with_aad(key, b"")
+    }
+
+    pub fn decrypt_with_aad(
+        &self,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<ZeroizeArray<N>, SecureMemError> {
+        let mut plaintext = ZeroizeArray::<N>::zeroed();
+        self.open_into(key, aad, &mut plaintext)?;
+
+        Ok(plaintext)
+    }
+
+    // `out` is zeroed whenever decryption fails
+    pub fn decrypt_into(&self, key: &Key, out: &mut ZeroizeArray<N>) -> Result<(), SecureMemError> {
+        self.open_into(key, b"", out)
+    }
+
+    fn open_into(
+        &self,
+        key: &Key,
+        aad: &[u8],
+        out: &mut ZeroizeArray<N>,
+    ) -> Result<(), SecureMemError> {
+        open_inline::<N, C>(
+            &self.ciphertext,
+            &self.tag,
+            &self.nonce,
+            self.key_commitment.as_ref(),
+            key,
+            aad,
+            out,
+        )
+    }
+}
+
+#[cfg(feature = "getrandom")]
+pub(crate) type SealedInline<const N: usize, C> = (
+    ZeroizeArray<N>,
+    Tag<C>,
+    Nonce<C>,
+    Option<[u8; KEY_COMMITMENT_LENGTH]>,
+);
+
+// Seals a copy of `plaintext` in place under a fresh nonce, shared with `DetachedCiphertext`
+#[cfg(feature = "getrandom")]
+pub(crate) fn seal_inline<const N: usize, C: Cipher>(
+    plaintext: &ZeroizeArray<N>,
+    key: &Key,
+    aad: &[u8],
+) -> Result<SealedInline<N, C>, SecureMemError> {
+    let nonce = crate::store::try_nonce_from_rng(&mut rand_core::OsRng)?;
+
+    // `sealed` is wiped when it drops on the error path
+    let mut sealed = plaintext.clone();
+    let tag = sealed
+        .expose_with_mut(|buffer| C::new(key).encrypt_in_place_detached(&nonce, aad, buffer))
+        .map_err(|_| SecureMemError::Encryption)?;
+    let key_commitment = match cfg!(feature = "key-commitment") {
+        true => Some(compute_key_commitment::<C>(key, &nonce)),
+        false => None,
+    };
+
+    Ok((sealed, tag, nonce, key_commitment))
+}
+
+// `out` is zeroed whenever opening fails
+pub(crate) fn open_inline<const N: usize, C: Cipher>(
+    ciphertext: &ZeroizeArray<N>,
+    tag: &Tag<C>,
+    nonce: &Nonce<C>,
+    key_commitment: Option<&[u8; KEY_COMMITMENT_LENGTH]>,
+    key: &Key,
+    aad: &[u8],
+    out: &mut ZeroizeArray<N>,
+) -> Result<(), SecureMemError> {
+    let committed = match key_commitment {
+        Some(key_commitment) => {
+            let mut expected = compute_key_commitment::<C>(key, nonce);
+            let matches: bool = expected.ct_eq(key_commitment).into();
+            expected.zeroize();
+
+            if !matches {
+                out.zeroize();
+
+                return Err(SecureMemError::WrongKey);
+            }
+
+            true
+        }
+        None => false,
+    };
+
+    let outcome = out.expose_with_mut(|buffer| {
+        buffer.copy_from_slice(ciphertext.expose_borrowed());
+        C::new(key).decrypt_in_place_detached(nonce, aad, buffer, tag)
+    });
+
+    outcome.map_err(|_| {
+        out.zeroize();
+
+        match committed {
+            true => SecureMemError::Corrupted,
+            false => SecureMemError::AuthenticationFailed,
+        }
+    })
+}
+
+impl<const N: usize, C: Cipher> Default for EncryptedMemStack<N, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, C: Cipher> Zeroize for EncryptedMemStack<N, C> {
+    fn zeroize(&mut self) {
+        self.ciphertext.zeroize();
+        self.tag.as_mut_slice().zeroize();
+        self.nonce.as_mut_slice().zeroize();
+        if let Some(key_commitment) = self.key_commitment.as_mut() {
+            key_commitment.zeroize();
+        }
+        self.key_commitment = None;
+    }
+}
+
+impl<const N: usize, C: Cipher> Drop for EncryptedMemStack<N, C> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<const N: usize, C: Cipher> ZeroizeOnDrop for EncryptedMemStack<N, C> {}
+
+impl<const N: usize, C: Cipher> core::fmt::Debug for EncryptedMemStack<N, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "EncryptedMemStack([REDACTED; {N}])")
+    }
+}
@@ -1,272 +1,2222 @@
+use crate::{Cipher, NonceCounter, SecureMemError, XChaCha8Poly1305};
+use alloc::{format, string::String, vec::Vec};
 use chacha20poly1305::{
     aead::{
         bytes::{BufMut, BytesMut},
-        AeadInPlace, KeyInit,
+        generic_array::{typenum::Unsigned, ArrayLength, GenericArray},
+        AeadCore, Nonce, Tag,
     },
-    Key, XChaCha8Poly1305, XNonce,
+    Key,
 };
-use nanorand::{BufferedRng, ChaCha8, Rng};
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+use rand_core::{CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 pub const XNONCE_LENGTH: usize = 24;
 pub const TAG_LENGTH: usize = 16;
-
-pub struct EncryptedMem<const N: usize> {
+pub const WIRE_MAGIC: [u8; 3] = *b"XSM";
+pub const WIRE_VERSION: u8 = 1;
+pub const WIRE_VERSION_KEY_COMMITTED: u8 = 2;
+pub const WIRE_HEADER_LENGTH: usize = WIRE_MAGIC.len() + 2;
+pub const KEY_COMMITMENT_LENGTH: usize = 32;
+
+#[cfg(feature = "getrandom")]
+pub(crate) const OS_RNG_FAILED: &str = "the operating system random number generator failed";
+const KEY_COMMITMENT_CONTEXT: &str = "xor-securemem 2023-01-01 EncryptedMem key commitment v1";
+const FINGERPRINT_CONTEXT: &str = "xor-securemem 2023-01-01 fingerprint v1";
+
+pub struct EncryptedMem<const N: usize, C: Cipher = XChaCha8Poly1305> {
     ciphertext: ZeroizeBytesArray<N>,
-    xnonce: XNonce,
+    nonce: Nonce<C>,
+    // Produced by `encrypt` only with the `key-commitment` feature, but always checked by `decrypt` when present
+    key_commitment: Option<[u8; KEY_COMMITMENT_LENGTH]>,
 }
 
-impl<const N: usize> EncryptedMem<N> {
+impl<const N: usize, C: Cipher> EncryptedMem<N, C> {
+    pub const NONCE_LENGTH: usize = <C as AeadCore>::NonceSize::USIZE;
+    pub const TAG_LENGTH: usize = <C as AeadCore>::TagSize::USIZE;
+
+    pub fn builder() -> EncryptedMemBuilder<N, C> {
+        EncryptedMemBuilder::new()
+    }
+
+    // Panics when the operating system RNG fails, `try_new` reports it instead
+    #[cfg(feature = "getrandom")]
     pub fn new() -> Self {
-        let mut nonce_buffer = [0u8; XNONCE_LENGTH];
-        let mut rng = BufferedRng::new(ChaCha8::new());
-        rng.fill(&mut nonce_buffer);
+        Self::builder().assemble(random_nonce::<C>())
+    }
 
-        let outcome = EncryptedMem {
-            ciphertext: ZeroizeBytesArray::with_additional_capacity(16),
-            xnonce: *XNonce::from_slice(&nonce_buffer), //TODO check if this is zeroed out,
-        };
+    #[cfg(feature = "getrandom")]
+    pub fn try_new() -> Result<Self, SecureMemError> {
+        Self::builder().rng(&mut OsRng).build()
+    }
 
-        nonce_buffer[..].copy_from_slice(&[0u8; XNONCE_LENGTH]);
+    // Reserves `capacity` bytes for the ciphertext, but never less than `N` plus the tag so sealing does not reallocate
+    #[cfg(feature = "getrandom")]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(N + Self::TAG_LENGTH);
 
-        outcome
+        Self::builder()
+            .capacity(capacity - N)
+            .assemble(random_nonce::<C>())
     }
 
+    #[deprecated(note = "use `with_capacity`, which takes the total capacity")]
+    #[cfg(feature = "getrandom")]
     pub fn new_with_added_capacity(capacity: usize) -> Self {
-        let mut nonce_buffer = [0u8; XNONCE_LENGTH];
-        let mut rng = BufferedRng::new(ChaCha8::new());
-        rng.fill(&mut nonce_buffer);
+        Self::builder()
+            .capacity(capacity)
+            .assemble(random_nonce::<C>())
+    }
 
-        let outcome = EncryptedMem {
-            ciphertext: ZeroizeBytesArray::with_additional_capacity(capacity),
-            xnonce: *XNonce::from_slice(&nonce_buffer), //TODO check if this is zeroed out,
-        };
+    pub fn from_parts(ciphertext: ZeroizeBytesArray<N>, nonce: Nonce<C>) -> Self {
+        EncryptedMem {
+            ciphertext,
+            nonce,
+            key_commitment: None,
+        }
+    }
+
+    pub fn from_parts_with_key_commitment(
+        ciphertext: ZeroizeBytesArray<N>,
+        nonce: Nonce<C>,
+        key_commitment: [u8; KEY_COMMITMENT_LENGTH],
+    ) -> Self {
+        EncryptedMem {
+            ciphertext,
+            nonce,
+            key_commitment: Some(key_commitment),
+        }
+    }
 
-        nonce_buffer[..].copy_from_slice(&[0u8; XNONCE_LENGTH]);
+    // Layout: `WIRE_MAGIC || WIRE_VERSION || algorithm || nonce || ciphertext || tag`, or with a key commitment
+    // `WIRE_MAGIC || WIRE_VERSION_KEY_COMMITTED || algorithm || nonce || key commitment || ciphertext || tag`
+    pub fn to_bytes(&self) -> ZeroizeBytes {
+        let mut buffer = BytesMut::with_capacity(
+            WIRE_HEADER_LENGTH + Self::NONCE_LENGTH + KEY_COMMITMENT_LENGTH + self.ciphertext.len(),
+        );
+
+        buffer.extend_from_slice(&WIRE_MAGIC);
+        match &self.key_commitment {
+            Some(key_commitment) => {
+                buffer.put_u8(WIRE_VERSION_KEY_COMMITTED);
+                buffer.put_u8(C::ALGORITHM);
+                buffer.extend_from_slice(self.nonce.as_slice());
+                buffer.extend_from_slice(key_commitment);
+            }
+            None => {
+                buffer.put_u8(WIRE_VERSION);
+                buffer.put_u8(C::ALGORITHM);
+                buffer.extend_from_slice(self.nonce.as_slice());
+            }
+        }
+        buffer.extend_from_slice(&self.ciphertext.0[..]);
+
+        ZeroizeBytes(buffer)
+    }
 
-        outcome
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SecureMemError> {
+        if data.len() < WIRE_HEADER_LENGTH + Self::NONCE_LENGTH + Self::TAG_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let (header, body) = data.split_at(WIRE_HEADER_LENGTH);
+        if wire_algorithm(header)? != C::ALGORITHM {
+            return Err(SecureMemError::AlgorithmMismatch);
+        }
+
+        let (nonce, body) = body.split_at(Self::NONCE_LENGTH);
+        let (key_commitment, ciphertext) = match header[WIRE_MAGIC.len()] {
+            WIRE_VERSION => (None, body),
+            WIRE_VERSION_KEY_COMMITTED if body.len() >= KEY_COMMITMENT_LENGTH => {
+                let (key_commitment, ciphertext) = body.split_at(KEY_COMMITMENT_LENGTH);
+                let mut commitment = [0u8; KEY_COMMITMENT_LENGTH];
+                commitment.copy_from_slice(key_commitment);
+
+                (Some(commitment), ciphertext)
+            }
+            WIRE_VERSION_KEY_COMMITTED => return Err(SecureMemError::InvalidLength),
+            _ => return Err(SecureMemError::InvalidHeader),
+        };
+        if ciphertext.len() != N + Self::TAG_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let mut buffer = BytesMut::with_capacity(N + Self::TAG_LENGTH);
+        buffer.extend_from_slice(ciphertext);
+
+        Ok(EncryptedMem {
+            ciphertext: ZeroizeBytesArray(buffer),
+            nonce: Nonce::<C>::clone_from_slice(nonce),
+            key_commitment,
+        })
     }
 
     pub fn ciphertext(&self) -> &ZeroizeBytesArray<N> {
         &self.ciphertext
     }
 
-    pub fn encrypt(&mut self, plaintext: &ZeroizeArray<N>, key: &Key) -> &mut Self {
-        let cipher = XChaCha8Poly1305::new(&key);
+    // The ciphertext length including the tag, zero until the first `encrypt`
+    pub fn len(&self) -> usize {
+        self.ciphertext.len()
+    }
 
-        let mut buffer = BytesMut::with_capacity(N + TAG_LENGTH); // Note: buffer needs 16-bytes overhead for auth tag
-        buffer.extend_from_slice(plaintext.expose_borrowed());
-        // Encrypt `buffer` in-place, replacing the plaintext contents with ciphertext
-        cipher
-            .encrypt_in_place(&self.xnonce, b"", &mut buffer) //TODO Check if tag is being added
-            .unwrap();
+    pub fn is_empty(&self) -> bool {
+        self.ciphertext.is_empty()
+    }
 
-        let mut ciphertext = ZeroizeBytesArray::with_additional_capacity(16);
+    // Room reserved for the ciphertext, at least `N` plus the tag unless built from an externally sized buffer
+    pub fn capacity(&self) -> usize {
+        self.ciphertext.capacity()
+    }
 
-        ciphertext.set(buffer);
+    pub fn nonce(&self) -> &Nonce<C> {
+        &self.nonce
+    }
 
-        self.ciphertext = ciphertext;
+    pub fn key_commitment(&self) -> Option<&[u8; KEY_COMMITMENT_LENGTH]> {
+        self.key_commitment.as_ref()
+    }
 
-        self
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+    ) -> Result<&mut Self, SecureMemError> {
+        self.encrypt_with_aad(plaintext, key, b"")
     }
 
-    pub fn decrypt(&mut self, key: &Key) -> BytesMut {
-        let cipher = XChaCha8Poly1305::new(&key);
+    // The plaintext is wiped before returning on every path, so afterwards it only exists in encrypted form
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_owned(
+        &mut self,
+        mut plaintext: ZeroizeArray<N>,
+        key: &Key,
+    ) -> Result<&mut Self, SecureMemError> {
+        let outcome = self.encrypt(&plaintext, key).map(|_| ());
+        plaintext.zeroize();
+
+        outcome.map(|_| self)
+    }
 
-        let mut buffer = BytesMut::with_capacity(N + TAG_LENGTH); // Note: buffer needs 16-bytes overhead for auth tag
-        buffer.extend_from_slice(self.ciphertext.expose());
+    #[cfg(feature = "getrandom")]
+    pub fn seal(plaintext: ZeroizeArray<N>, key: &Key) -> Result<Self, SecureMemError> {
+        let mut sealed = Self::new();
+        sealed.encrypt_owned(plaintext, key)?;
 
-        // Decrypt `buffer` in-place, replacing its ciphertext context with the original plaintext
-        cipher
-            .decrypt_in_place(&self.xnonce, b"", &mut buffer)
-            .unwrap();
+        Ok(sealed)
+    }
 
-        buffer
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_aad(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<&mut Self, SecureMemError> {
+        // A fresh nonce per call so that re-encrypting into the same `EncryptedMem` never reuses a (key, nonce) pair
+        let nonce = try_nonce_from_rng(&mut OsRng)?;
+
+        self.encrypt_under_nonce(plaintext, key, aad, nonce)
     }
-}
 
-pub struct ZeroizeArray<const N: usize>([u8; N]);
+    // Takes the next nonce from `counter` instead of a random one, so a receiver can detect replays and reordering by
+    // the counter embedded in `nonce()`
+    pub fn encrypt_with_counter(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+        counter: &mut NonceCounter<C>,
+    ) -> Result<&mut Self, SecureMemError> {
+        let nonce = counter.next_nonce()?;
+
+        self.encrypt_under_nonce(plaintext, key, b"", nonce)
+    }
 
-impl<const N: usize> ZeroizeArray<N> {
-    pub fn new(value: [u8; N]) -> Self {
-        ZeroizeArray(value)
+    fn encrypt_under_nonce(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        key: &Key,
+        aad: &[u8],
+        nonce: Nonce<C>,
+    ) -> Result<&mut Self, SecureMemError> {
+        self.encrypt_with_cipher(plaintext, &C::new(key), key, aad, nonce)
     }
 
-    pub fn zeroed() -> Self {
-        ZeroizeArray([0u8; N])
+    // `cipher` has to have been initialized from `key`, which is still needed for the key commitment
+    pub(crate) fn encrypt_with_cipher(
+        &mut self,
+        plaintext: &ZeroizeArray<N>,
+        cipher: &C,
+        key: &Key,
+        aad: &[u8],
+        nonce: Nonce<C>,
+    ) -> Result<&mut Self, SecureMemError> {
+        let mut buffer = BytesMut::with_capacity(N + Self::TAG_LENGTH); // Note: buffer needs the cipher's tag overhead
+        buffer.extend_from_slice(plaintext.expose_borrowed());
+        // Encrypt `buffer` in-place, replacing the plaintext contents with ciphertext
+        if cipher.encrypt_in_place(&nonce, aad, &mut buffer).is_err() {
+            wipe_bytes_mut(&mut buffer);
+
+            return Err(SecureMemError::Encryption);
+        }
+        self.replace_sealed(buffer, key, nonce);
+
+        Ok(self)
     }
 
-    pub fn fill_from_slice(&mut self, value: [u8; N]) -> &mut Self {
-        self.0.copy_from_slice(&value);
+    fn replace_sealed(&mut self, buffer: BytesMut, key: &Key, nonce: Nonce<C>) {
+        // Move the buffer in instead of copying it so no un-wiped intermediate copy of the ciphertext is left behind
+        self.ciphertext.zeroize();
+        self.ciphertext = ZeroizeBytesArray(buffer);
+        if let Some(key_commitment) = self.key_commitment.as_mut() {
+            key_commitment.zeroize();
+        }
+        self.key_commitment = if cfg!(feature = "key-commitment") {
+            Some(compute_key_commitment::<C>(key, &nonce))
+        } else {
+            None
+        };
+        self.nonce.as_mut_slice().zeroize();
+        self.nonce = nonce;
+    }
 
-        self
+    // Re-encrypts under `new_key` with a fresh nonce, the transient plaintext is wiped on every path and `self` is
+    // left as it was when decryption or encryption fails
+    #[cfg(feature = "getrandom")]
+    pub fn rotate_key(&mut self, old_key: &Key, new_key: &Key) -> Result<(), SecureMemError> {
+        let mut plaintext = ZeroizeArray::<N>::zeroed();
+        let outcome = self
+            .open_into(old_key, b"", &mut plaintext)
+            .and_then(|_| self.encrypt(&plaintext, new_key).map(|_| ()));
+        plaintext.zeroize();
+
+        outcome
     }
 
-    pub fn expose(&self) -> [u8; N] {
-        self.0
+    // Refreshes the ciphertext under the same key so memory dumps taken at different times cannot be correlated
+    #[cfg(feature = "getrandom")]
+    pub fn rotate_nonce(&mut self, key: &Key) -> Result<(), SecureMemError> {
+        self.rotate_key(key, key)
     }
 
-    pub fn expose_borrowed(&self) -> &[u8; N] {
-        &self.0
+    // An independent copy re-encrypted under a fresh nonce, a bitwise clone would share the nonce with `self`
+    #[cfg(feature = "getrandom")]
+    pub fn duplicate(&self, key: &Key) -> Result<Self, SecureMemError> {
+        let mut plaintext = ZeroizeArray::<N>::zeroed();
+        self.open_into(key, b"", &mut plaintext)?;
+
+        Self::seal(plaintext, key)
     }
 
-    pub fn clone(&self) -> ZeroizeArray<N> {
-        Self(self.0)
+    pub fn decrypt(&self, key: &Key) -> Result<ZeroizeArray<N>, SecureMemError> {
+        self.decrypt_with_aad(key, b"")
     }
 
-    pub fn chacha_key(&self) -> &Key {
-        Key::from_slice(self.0.as_slice())
+    pub fn decrypt_with_aad(
+        &self,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<ZeroizeArray<N>, SecureMemError> {
+        let mut plaintext = ZeroizeArray::<N>::zeroed();
+        self.open_into(key, aad, &mut plaintext)?;
+
+        Ok(plaintext)
     }
 
-    pub fn own(self) -> Self {
-        self
+    // The plaintext only ever lives in a buffer that is wiped when `f` returns or unwinds
+    pub fn decrypt_with<F, R>(&self, key: &Key, f: F) -> Result<R, SecureMemError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let mut scratch = ZeroizeArray::<N>::zeroed();
+
+        self.decrypt_with_scratch(key, &mut scratch, f)
+    }
+
+    fn decrypt_with_scratch<F, R>(
+        &self,
+        key: &Key,
+        scratch: &mut ZeroizeArray<N>,
+        f: F,
+    ) -> Result<R, SecureMemError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        struct WipeOnDrop<'a, const N: usize>(&'a mut ZeroizeArray<N>);
+
+        impl<const N: usize> Drop for WipeOnDrop<'_, N> {
+            fn drop(&mut self) {
+                self.0.zeroize()
+            }
+        }
+
+        let guard = WipeOnDrop(scratch);
+        self.open_into(key, b"", guard.0)?;
+
+        Ok(f(&guard.0 .0[..]))
     }
 
-    pub fn insert(&mut self, index: usize, value: u8) -> &mut Self {
-        self.0[index] = value;
+    // Checks the tag and key commitment only, the plaintext lands in a stack scratch that is wiped when it drops
+    pub fn verify(&self, key: &Key) -> Result<(), SecureMemError> {
+        let mut scratch = ZeroizeArray::<N>::zeroed();
 
-        self
+        self.open_into(key, b"", &mut scratch)
     }
 
-    pub fn csprng() -> Self {
-        let mut buffer = [0u8; N];
-        let mut rng = BufferedRng::new(ChaCha8::new());
-        rng.fill(&mut buffer);
+    // Decrypts without touching the heap, `out` is zeroed whenever decryption fails
+    pub fn decrypt_into(&self, key: &Key, out: &mut ZeroizeArray<N>) -> Result<(), SecureMemError> {
+        self.open_into(key, b"", out)
+    }
 
-        let csprng = ZeroizeArray(buffer);
+    fn open_into(
+        &self,
+        key: &Key,
+        aad: &[u8],
+        out: &mut ZeroizeArray<N>,
+    ) -> Result<(), SecureMemError> {
+        self.open_with_cipher(&C::new(key), key, aad, out)
+    }
+
+    pub(crate) fn open_with_cipher(
+        &self,
+        cipher: &C,
+        key: &Key,
+        aad: &[u8],
+        out: &mut ZeroizeArray<N>,
+    ) -> Result<(), SecureMemError> {
+        let outcome = self.open_in_place(cipher, key, aad, out);
+        if outcome.is_err() {
+            out.zeroize();
+        }
+
+        outcome
+    }
+
+    fn open_in_place(
+        &self,
+        cipher: &C,
+        key: &Key,
+        aad: &[u8],
+        out: &mut ZeroizeArray<N>,
+    ) -> Result<(), SecureMemError> {
+        if self.ciphertext.len() != N + Self::TAG_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        // The tag is copied to the stack and the ciphertext straight into `out`, which is decrypted in-place
+        let (ciphertext, tag) = self.ciphertext.expose().split_at(N);
+        let tag = Tag::<C>::clone_from_slice(tag);
+
+        self.open_detached(cipher, key, aad, ciphertext, &tag, out)
+    }
+
+    fn open_detached(
+        &self,
+        cipher: &C,
+        key: &Key,
+        aad: &[u8],
+        ciphertext: &[u8],
+        tag: &Tag<C>,
+        out: &mut ZeroizeArray<N>,
+    ) -> Result<(), SecureMemError> {
+        // With a key commitment a wrong key is told apart from a corrupted ciphertext before running the AEAD
+        let committed = match &self.key_commitment {
+            Some(key_commitment) => {
+                let mut expected = compute_key_commitment::<C>(key, &self.nonce);
+                let matches: bool = expected.ct_eq(key_commitment).into();
+                expected.zeroize();
+
+                if !matches {
+                    return Err(SecureMemError::WrongKey);
+                }
+
+                true
+            }
+            None => false,
+        };
 
-        buffer.copy_from_slice(&[0u8; N]);
+        out.0.copy_from_slice(ciphertext);
+        cipher
+            .decrypt_in_place_detached(&self.nonce, aad, &mut out.0, tag)
+            .map_err(|_| match committed {
+                true => SecureMemError::Corrupted,
+                false => SecureMemError::AuthenticationFailed,
+            })
+    }
+}
 
-        csprng
+#[cfg(feature = "getrandom")]
+impl<const N: usize, C: Cipher> Default for EncryptedMem<N, C> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<const N: usize> Zeroize for ZeroizeArray<N> {
+impl<const N: usize, C: Cipher> Zeroize for EncryptedMem<N, C> {
     fn zeroize(&mut self) {
-        self.0[..].copy_from_slice(&[0u8; N]);
+        self.ciphertext.zeroize();
+        self.nonce.as_mut_slice().zeroize();
+        if let Some(key_commitment) = self.key_commitment.as_mut() {
+            key_commitment.zeroize();
+        }
+        self.key_commitment = None;
     }
 }
 
-impl<const N: usize> Drop for ZeroizeArray<N> {
+impl<const N: usize, C: Cipher> Drop for EncryptedMem<N, C> {
     fn drop(&mut self) {
         self.zeroize()
     }
 }
 
-impl<const N: usize> ZeroizeOnDrop for ZeroizeArray<N> {}
+impl<const N: usize, C: Cipher> ZeroizeOnDrop for EncryptedMem<N, C> {}
+
+// The nonce is public and printed in hex, the ciphertext and key commitment only by length
+impl<const N: usize, C: Cipher> core::fmt::Debug for EncryptedMem<N, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EncryptedMem")
+            .field("algorithm", &C::ALGORITHM)
+            .field("nonce", &LowerHex(&self.nonce))
+            .field("ciphertext", &Redacted(self.ciphertext.len()))
+            .field(
+                "key_commitment",
+                &self
+                    .key_commitment
+                    .as_ref()
+                    .map(|_| Redacted(KEY_COMMITMENT_LENGTH)),
+            )
+            .finish()
+    }
+}
 
-pub struct ZeroizeBytesArray<const N: usize>(BytesMut);
+// A variable length counterpart of `EncryptedMem` for secrets whose length is only known at runtime
+pub struct EncryptedBytes<C: Cipher = XChaCha8Poly1305> {
+    ciphertext: ZeroizeBytes,
+    nonce: Nonce<C>,
+    key_commitment: Option<[u8; KEY_COMMITMENT_LENGTH]>,
+}
 
-impl<const N: usize> ZeroizeBytesArray<N> {
+impl<C: Cipher> EncryptedBytes<C> {
+    pub const NONCE_LENGTH: usize = <C as AeadCore>::NonceSize::USIZE;
+    pub const TAG_LENGTH: usize = <C as AeadCore>::TagSize::USIZE;
+
+    #[cfg(feature = "getrandom")]
     pub fn new() -> Self {
-        ZeroizeBytesArray(BytesMut::with_capacity(N))
+        EncryptedBytes {
+            ciphertext: ZeroizeBytes::new(),
+            nonce: random_nonce::<C>(),
+            key_commitment: None,
+        }
     }
 
-    pub fn set(&mut self, value: BytesMut) -> &mut Self {
-        self.0.put(&value[..]);
+    pub fn ciphertext(&self) -> &ZeroizeBytes {
+        &self.ciphertext
+    }
 
-        self
+    pub fn nonce(&self) -> &Nonce<C> {
+        &self.nonce
     }
 
-    pub fn with_additional_capacity(capacity: usize) -> Self {
-        ZeroizeBytesArray(BytesMut::with_capacity(N + capacity))
+    pub fn key_commitment(&self) -> Option<&[u8; KEY_COMMITMENT_LENGTH]> {
+        self.key_commitment.as_ref()
     }
 
-    pub fn expose(&self) -> &BytesMut {
-        &self.0
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt(
+        &mut self,
+        plaintext: &ZeroizeBytes,
+        key: &Key,
+    ) -> Result<&mut Self, SecureMemError> {
+        self.encrypt_with_aad(plaintext, key, b"")
     }
 
-    pub fn clone(&self) -> ZeroizeBytesArray<N> {
-        Self(self.0.clone())
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt_with_aad(
+        &mut self,
+        plaintext: &ZeroizeBytes,
+        key: &Key,
+        aad: &[u8],
+    ) -> Result<&mut Self, SecureMemError> {
+        let cipher = C::new(key);
+        let nonce = try_nonce_from_rng(&mut OsRng)?;
+
+        // Sized for this plaintext, so a longer value gets a new buffer and the old one is wiped below
+        let mut buffer = BytesMut::with_capacity(plaintext.len() + Self::TAG_LENGTH);
+        buffer.extend_from_slice(&plaintext.0[..]);
+        if cipher.encrypt_in_place(&nonce, aad, &mut buffer).is_err() {
+            wipe_bytes_mut(&mut buffer);
+
+            return Err(SecureMemError::Encryption);
+        }
+
+        self.ciphertext.zeroize();
+        self.ciphertext = ZeroizeBytes(buffer);
+        if let Some(key_commitment) = self.key_commitment.as_mut() {
+            key_commitment.zeroize();
+        }
+        self.key_commitment = if cfg!(feature = "key-commitment") {
+            Some(compute_key_commitment::<C>(key, &nonce))
+        } else {
+            None
+        };
+        self.nonce.as_mut_slice().zeroize();
+        self.nonce = nonce;
+
+        Ok(self)
     }
 
-    pub fn chacha_key(&self) -> &Key {
-        Key::from_slice(&self.0[..])
+    pub fn decrypt(&self, key: &Key) -> Result<ZeroizeBytes, SecureMemError> {
+        self.decrypt_with_aad(key, b"")
     }
 
-    pub fn csprng() -> Self {
-        let mut buffer = [0u8; N];
-        let mut rng = BufferedRng::new(ChaCha8::new());
-        rng.fill(&mut buffer);
+    pub fn decrypt_with_aad(&self, key: &Key, aad: &[u8]) -> Result<ZeroizeBytes, SecureMemError> {
+        if self.ciphertext.len() < Self::TAG_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let committed = match &self.key_commitment {
+            Some(key_commitment) => {
+                let mut expected = compute_key_commitment::<C>(key, &self.nonce);
+                let matches: bool = expected.ct_eq(key_commitment).into();
+                expected.zeroize();
+
+                if !matches {
+                    return Err(SecureMemError::WrongKey);
+                }
 
-        let mut bytes_buffer = BytesMut::with_capacity(N);
+                true
+            }
+            None => false,
+        };
+
+        let cipher = C::new(key);
 
-        bytes_buffer.put(&buffer[..]);
+        let mut buffer = BytesMut::with_capacity(self.ciphertext.len());
+        buffer.extend_from_slice(&self.ciphertext.0[..]);
 
-        buffer.copy_from_slice(&[0u8; N]);
+        match cipher.decrypt_in_place(&self.nonce, aad, &mut buffer) {
+            Ok(()) => Ok(ZeroizeBytes(buffer)),
+            Err(_) => {
+                wipe_bytes_mut(&mut buffer);
 
-        ZeroizeBytesArray(bytes_buffer)
+                Err(match committed {
+                    true => SecureMemError::Corrupted,
+                    false => SecureMemError::AuthenticationFailed,
+                })
+            }
+        }
     }
 }
 
-impl<const N: usize> Zeroize for ZeroizeBytesArray<N> {
+#[cfg(feature = "getrandom")]
+impl<C: Cipher> Default for EncryptedBytes<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Cipher> Zeroize for EncryptedBytes<C> {
     fn zeroize(&mut self) {
-        self.0.clear()
+        self.ciphertext.zeroize();
+        self.nonce.as_mut_slice().zeroize();
+        if let Some(key_commitment) = self.key_commitment.as_mut() {
+            key_commitment.zeroize();
+        }
+        self.key_commitment = None;
     }
 }
 
-impl<const N: usize> Drop for ZeroizeBytesArray<N> {
+impl<C: Cipher> Drop for EncryptedBytes<C> {
     fn drop(&mut self) {
         self.zeroize()
     }
 }
 
-impl<const N: usize> ZeroizeOnDrop for ZeroizeBytesArray<N> {}
+impl<C: Cipher> ZeroizeOnDrop for EncryptedBytes<C> {}
 
-pub struct ZeroizeBytes(BytesMut);
+// Holds UTF-8 secrets such as tokens and passwords, the plaintext `String` is consumed and shredded on encryption
+pub struct EncryptedString<C: Cipher = XChaCha8Poly1305>(EncryptedBytes<C>);
 
-impl ZeroizeBytes {
+impl<C: Cipher> EncryptedString<C> {
+    #[cfg(feature = "getrandom")]
     pub fn new() -> Self {
-        ZeroizeBytes(BytesMut::new())
+        EncryptedString(EncryptedBytes::new())
     }
 
-    pub fn set(&mut self, value: BytesMut) -> &mut Self {
-        self.0.put(&value[..]);
+    pub fn encrypted_bytes(&self) -> &EncryptedBytes<C> {
+        &self.0
+    }
 
-        self
+    // The whole heap buffer of `plaintext`, including spare capacity, is wiped before this returns
+    #[cfg(feature = "getrandom")]
+    pub fn encrypt(
+        &mut self,
+        mut plaintext: String,
+        key: &Key,
+    ) -> Result<&mut Self, SecureMemError> {
+        let mut bytes = ZeroizeBytes::with_capacity(plaintext.len());
+        bytes.set(BytesMut::from(plaintext.as_bytes()));
+        plaintext.zeroize();
+
+        self.0.encrypt(&bytes, key)?;
+
+        Ok(self)
     }
 
-    pub fn new_with_capacity(capacity: usize) -> Self {
-        ZeroizeBytes(BytesMut::with_capacity(capacity))
+    pub fn decrypt(&self, key: &Key) -> Result<ZeroizeString, SecureMemError> {
+        self.with_str(key, |plaintext| ZeroizeString(String::from(plaintext)))
     }
 
-    pub fn expose(&self) -> &BytesMut {
+    // The decrypted bytes are wiped when `f` returns
+    pub fn with_str<R>(&self, key: &Key, f: impl FnOnce(&str) -> R) -> Result<R, SecureMemError> {
+        let plaintext = self.0.decrypt(key)?;
+        let plaintext =
+            core::str::from_utf8(&plaintext.0[..]).map_err(|_| SecureMemError::InvalidString)?;
+
+        Ok(f(plaintext))
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl<C: Cipher> Default for EncryptedString<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ZeroizeString(String);
+
+impl ZeroizeString {
+    pub fn new(value: String) -> Self {
+        ZeroizeString(value)
+    }
+
+    pub fn expose(&self) -> &str {
         &self.0
     }
+}
 
-    pub fn clone(&self) -> ZeroizeBytes {
-        Self(self.0.clone())
+impl Zeroize for ZeroizeString {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
     }
+}
 
-    pub fn chacha_key(&self) -> &Key {
-        Key::from_slice(&self.0[..])
+impl Drop for ZeroizeString {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for ZeroizeString {}
+
+impl core::fmt::Debug for ZeroizeString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ZeroizeString([REDACTED])")
     }
+}
 
-    pub fn csprng<const BUFFER_SIZE: usize>() -> Self {
-        let mut buffer = [0u8; BUFFER_SIZE];
-        let mut rng = BufferedRng::new(ChaCha8::new());
-        rng.fill(&mut buffer);
+// A plaintext UTF-8 secret in a zeroizing buffer, the contents are validated once when it is constructed
+pub struct SecureString(ZeroizeBytes);
+
+impl SecureString {
+    // Takes over the buffer without copying it
+    pub fn from_utf8(bytes: ZeroizeBytes) -> Result<Self, SecureMemError> {
+        core::str::from_utf8(&bytes.0[..]).map_err(|_| SecureMemError::InvalidString)?;
+
+        Ok(SecureString(bytes))
+    }
 
-        let mut bytes_buffer = BytesMut::with_capacity(BUFFER_SIZE);
+    pub fn as_str(&self) -> &str {
+        // SAFETY: the bytes were validated as UTF-8 on construction and are never mutated afterwards
+        unsafe { core::str::from_utf8_unchecked(&self.0 .0[..]) }
+    }
 
-        bytes_buffer.put(&buffer[..]);
+    pub fn expose_with<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        f(self.as_str())
+    }
 
-        buffer.copy_from_slice(&[0u8; BUFFER_SIZE]);
+    pub fn len(&self) -> usize {
+        self.0 .0.len()
+    }
 
-        ZeroizeBytes(bytes_buffer)
+    pub fn is_empty(&self) -> bool {
+        self.0 .0.is_empty()
     }
 }
 
-impl Zeroize for ZeroizeBytes {
-    fn zeroize(&mut self) {
-        self.0.clear()
+impl core::str::FromStr for SecureString {
+    type Err = SecureMemError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut bytes = ZeroizeBytes::with_capacity(value.len());
+        bytes.0.extend_from_slice(value.as_bytes());
+
+        Ok(SecureString(bytes))
     }
 }
 
-impl Drop for ZeroizeBytes {
-    fn drop(&mut self) {
-        self.zeroize()
+impl core::fmt::Debug for SecureString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("SecureString([REDACTED])")
     }
 }
 
-impl ZeroizeOnDrop for ZeroizeBytes {}
+pub struct EncryptedMemBuilder<const N: usize, C: Cipher = XChaCha8Poly1305> {
+    capacity: usize,
+    nonce: Option<Nonce<C>>,
+    ciphertext: Option<ZeroizeBytesArray<N>>,
+    allow_zero_nonce: bool,
+    rng_error: Option<SecureMemError>,
+}
+
+impl<const N: usize, C: Cipher> EncryptedMemBuilder<N, C> {
+    pub fn new() -> Self {
+        EncryptedMemBuilder {
+            capacity: EncryptedMem::<N, C>::TAG_LENGTH,
+            nonce: None,
+            ciphertext: None,
+            allow_zero_nonce: false,
+            rng_error: None,
+        }
+    }
+
+    // Additional capacity reserved past `N` bytes, which defaults to the cipher's tag length
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+
+        self
+    }
+
+    // Draws the initial nonce from `rng` instead of the operating system RNG, a failure is reported by `build`
+    pub fn rng<R: RngCore + CryptoRng>(mut self, rng: &mut R) -> Self {
+        match try_nonce_from_rng(rng) {
+            Ok(nonce) => self.nonce = Some(nonce),
+            Err(error) => self.rng_error = Some(error),
+        }
+
+        self
+    }
+
+    // For blobs sealed outside this crate, `encrypt` still draws a fresh nonce every call
+    pub fn nonce(mut self, nonce: Nonce<C>) -> Self {
+        self.nonce = Some(nonce);
+
+        self
+    }
+
+    pub fn ciphertext(mut self, ciphertext: ZeroizeBytesArray<N>) -> Self {
+        self.ciphertext = Some(ciphertext);
+
+        self
+    }
+
+    // An all zero nonce almost always means an uninitialized buffer, so it is rejected unless allowed here
+    pub fn allow_zero_nonce(mut self) -> Self {
+        self.allow_zero_nonce = true;
+
+        self
+    }
+
+    pub fn build(mut self) -> Result<EncryptedMem<N, C>, SecureMemError> {
+        if let Some(error) = self.rng_error {
+            return Err(error);
+        }
+        if let Some(nonce) = &self.nonce {
+            if !self.allow_zero_nonce && nonce.iter().all(|byte| *byte == 0) {
+                return Err(SecureMemError::InvalidNonce);
+            }
+        }
+
+        let nonce = match self.nonce.take() {
+            Some(nonce) => nonce,
+            #[cfg(feature = "getrandom")]
+            None => try_nonce_from_rng(&mut OsRng)?,
+            // Without `getrandom` there is no operating system RNG to fall back on
+            #[cfg(not(feature = "getrandom"))]
+            None => return Err(SecureMemError::RandomnessUnavailable),
+        };
+
+        Ok(self.assemble(nonce))
+    }
+
+    fn assemble(self, nonce: Nonce<C>) -> EncryptedMem<N, C> {
+        let capacity = self.capacity;
+
+        EncryptedMem {
+            ciphertext: self
+                .ciphertext
+                .unwrap_or_else(|| ZeroizeBytesArray::with_capacity(N + capacity)),
+            nonce,
+            key_commitment: None,
+        }
+    }
+}
+
+impl<const N: usize, C: Cipher> Default for EncryptedMemBuilder<N, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ZeroizeArray<const N: usize>([u8; N]);
+
+impl<const N: usize> ZeroizeArray<N> {
+    pub fn new(value: [u8; N]) -> Self {
+        ZeroizeArray(value)
+    }
+
+    pub fn zeroed() -> Self {
+        ZeroizeArray([0u8; N])
+    }
+
+    // The argument is a copy of the secret, it is wiped before returning
+    pub fn fill_from_slice(&mut self, mut value: [u8; N]) -> &mut Self {
+        self.0.copy_from_slice(&value);
+        value.zeroize();
+
+        self
+    }
+
+    // The caller still owns `src` and is responsible for wiping it
+    pub fn try_fill_from_slice(&mut self, src: &[u8]) -> Result<&mut Self, SecureMemError> {
+        if src.len() != N {
+            return Err(SecureMemError::LengthMismatch {
+                expected: N,
+                actual: src.len(),
+            });
+        }
+        self.0.copy_from_slice(src);
+
+        Ok(self)
+    }
+
+    // Reads exactly `N` bytes straight into the array, so a key file never passes through an intermediate buffer.
+    // A short or failed read wipes whatever was already read in.
+    #[cfg(feature = "std")]
+    pub fn fill_from_reader(
+        &mut self,
+        reader: &mut impl std::io::Read,
+    ) -> Result<&mut Self, SecureMemError> {
+        match reader.read_exact(&mut self.0) {
+            Ok(()) => Ok(self),
+            Err(error) => {
+                self.0.zeroize();
+
+                Err(match error.kind() {
+                    std::io::ErrorKind::UnexpectedEof => SecureMemError::InvalidLength,
+                    _ => SecureMemError::Io,
+                })
+            }
+        }
+    }
+
+    // Hands out a copy that the caller has to wipe, prefer `expose_with` which never copies the secret
+    pub fn expose(&self) -> [u8; N] {
+        self.0
+    }
+
+    pub fn expose_borrowed(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    // The preferred way to touch the secret bytes, they are only reachable for the duration of `f` and no copy is made
+    pub fn expose_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.0)
+    }
+
+    pub fn expose_with_mut<R>(&mut self, f: impl FnOnce(&mut [u8]) -> R) -> R {
+        f(&mut self.0)
+    }
+
+    pub fn own(self) -> Self {
+        self
+    }
+
+    // Swaps the contents byte by byte in place, so neither secret passes through a temporary array
+    pub fn swap(&mut self, other: &mut ZeroizeArray<N>) -> &mut Self {
+        self.0.swap_with_slice(&mut other.0);
+
+        self
+    }
+
+    // Moves the contents into a new array and leaves `self` zeroed
+    pub fn take(&mut self) -> ZeroizeArray<N> {
+        let mut taken = ZeroizeArray::zeroed();
+        self.swap(&mut taken);
+
+        taken
+    }
+
+    // Moves the bytes out and leaves zeros in their place, the caller owns wiping the returned array. `Drop` is
+    // skipped since there is nothing left to wipe.
+    pub fn into_inner(self) -> [u8; N] {
+        let mut array = core::mem::ManuallyDrop::new(self);
+
+        core::mem::replace(&mut array.0, [0u8; N])
+    }
+
+    pub fn insert(&mut self, index: usize, value: u8) -> Result<&mut Self, SecureMemError> {
+        let byte = self
+            .0
+            .get_mut(index)
+            .ok_or(SecureMemError::IndexOutOfBounds)?;
+        *byte = value;
+
+        Ok(self)
+    }
+
+    // Constant time, every byte is folded in whether or not an earlier one was non-zero
+    pub fn ct_is_zero(&self) -> Choice {
+        ct_is_zero(&self.0[..])
+    }
+
+    // For checking against untrusted input such as a received tag. Only the lengths are compared up front, the
+    // bytes themselves are always compared in full.
+    pub fn ct_eq_slice(&self, other: &[u8]) -> bool {
+        self.0[..].ct_eq(other).into()
+    }
+
+    // A domain separated BLAKE3 digest, `blake3::derive_key(FINGERPRINT_CONTEXT, bytes)`, that identifies a key in
+    // logs and deduplication without exposing it. Only safe to show for high entropy secrets, a low entropy one such
+    // as a password can be brute forced from its fingerprint.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        fingerprint(&self.0[..])
+    }
+
+    // The first 8 bytes of `fingerprint` as lowercase hex
+    pub fn fingerprint_hex(&self) -> String {
+        fingerprint_hex(&self.0[..])
+    }
+
+    pub fn get(&self, index: usize) -> Option<u8> {
+        self.0.get(index).copied()
+    }
+
+    // Byte wise xor with no data dependent branches, for mixing key shares or applying a one-time mask
+    pub fn xor_in_place(&mut self, other: &ZeroizeArray<N>) -> &mut Self {
+        self.0
+            .iter_mut()
+            .zip(&other.0)
+            .for_each(|(byte, mask)| *byte ^= mask);
+
+        self
+    }
+
+    // The result reuses the storage of `self`, `other` is wiped before returning
+    pub fn xor(mut self, mut other: ZeroizeArray<N>) -> ZeroizeArray<N> {
+        self.xor_in_place(&other);
+        other.zeroize();
+
+        self
+    }
+
+    /// Moves the first `A` bytes and the remaining `B` bytes into two new arrays and wipes `self`, a split whose
+    /// halves do not add up to `N` is rejected at compile time
+    ///
+    /// ```compile_fail
+    /// let key = xor_securemem::ZeroizeArray::<64>::zeroed();
+    /// let (_, _) = key.split::<32, 16>();
+    /// ```
+    pub fn split<const A: usize, const B: usize>(self) -> (ZeroizeArray<A>, ZeroizeArray<B>) {
+        const { assert!(A + B == N, "the halves must add up to the array length") };
+
+        match self.try_split() {
+            Ok(halves) => halves,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    // Like `split` but for lengths that are only checked at runtime, `self` is wiped either way
+    pub fn try_split<const A: usize, const B: usize>(
+        mut self,
+    ) -> Result<(ZeroizeArray<A>, ZeroizeArray<B>), SecureMemError> {
+        if A + B != N {
+            self.zeroize();
+
+            return Err(SecureMemError::LengthMismatch {
+                expected: N,
+                actual: A + B,
+            });
+        }
+
+        let mut first = ZeroizeArray::<A>::zeroed();
+        let mut second = ZeroizeArray::<B>::zeroed();
+        first.0.copy_from_slice(&self.0[..A]);
+        second.0.copy_from_slice(&self.0[A..]);
+        self.zeroize();
+
+        Ok((first, second))
+    }
+
+    /// The inverse of `split`, both inputs are wiped once copied and an `OUT` other than `N + M` is rejected at
+    /// compile time
+    ///
+    /// ```compile_fail
+    /// let salt = xor_securemem::ZeroizeArray::<16>::zeroed();
+    /// let ikm = xor_securemem::ZeroizeArray::<32>::zeroed();
+    /// let _ = salt.concat::<32, 64>(ikm);
+    /// ```
+    pub fn concat<const M: usize, const OUT: usize>(
+        self,
+        other: ZeroizeArray<M>,
+    ) -> ZeroizeArray<OUT> {
+        const {
+            assert!(
+                N + M == OUT,
+                "the output length must be the sum of both lengths"
+            )
+        };
+
+        match self.try_concat(other) {
+            Ok(joined) => joined,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    pub fn try_concat<const M: usize, const OUT: usize>(
+        mut self,
+        mut other: ZeroizeArray<M>,
+    ) -> Result<ZeroizeArray<OUT>, SecureMemError> {
+        if N + M != OUT {
+            self.zeroize();
+            other.zeroize();
+
+            return Err(SecureMemError::LengthMismatch {
+                expected: OUT,
+                actual: N + M,
+            });
+        }
+
+        let mut joined = ZeroizeArray::<OUT>::zeroed();
+        joined.0[..N].copy_from_slice(&self.0);
+        joined.0[N..].copy_from_slice(&other.0);
+        self.zeroize();
+        other.zeroize();
+
+        Ok(joined)
+    }
+
+    // Panics when the operating system RNG fails
+    #[cfg(feature = "getrandom")]
+    pub fn csprng() -> Self {
+        Self::try_csprng().expect(OS_RNG_FAILED)
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn try_csprng() -> Result<Self, SecureMemError> {
+        Self::try_csprng_from(&mut OsRng)
+    }
+
+    // Fills the array in place from any cryptographic RNG, so no copy of the random bytes is left on the stack
+    pub fn try_csprng_from<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, SecureMemError> {
+        let mut csprng = ZeroizeArray::zeroed();
+        try_fill_random(rng, &mut csprng.0)?;
+
+        Ok(csprng)
+    }
+
+    /// HKDF-SHA256 (RFC 5869) with `self` as the input keying material, the same `salt` and `info` always give the
+    /// same subkey. Both steps run on the wiping `HmacSha256`, so the pseudorandom key and every expanded block are
+    /// wiped once the subkey is written. HKDF-SHA256 cannot produce more than 8160 bytes, so a longer `M` is rejected
+    /// at compile time
+    ///
+    /// ```compile_fail
+    /// let master = xor_securemem::ZeroizeArray::<32>::zeroed();
+    /// let _ = master.derive_subkey::<8161>(None, b"too long");
+    /// ```
+    #[cfg(feature = "hkdf")]
+    pub fn derive_subkey<const M: usize>(
+        &self,
+        salt: Option<&[u8]>,
+        info: &[u8],
+    ) -> ZeroizeArray<M> {
+        const { assert!(M <= 255 * 32, "HKDF-SHA256 output is at most 8160 bytes") };
+
+        // A missing salt is a block of zeros, which HMAC pads to the same key as an empty one
+        let mut extract = crate::HmacSha256::with_key(salt.unwrap_or_default());
+        extract.update(&self.0);
+        let prk = extract.finalize();
+
+        let mut subkey = ZeroizeArray::<M>::zeroed();
+        let mut block = ZeroizeArray::<32>::zeroed();
+        subkey
+            .0
+            .chunks_mut(32)
+            .enumerate()
+            .for_each(|(index, chunk)| {
+                let mut expand = crate::HmacSha256::new(&prk);
+                if index > 0 {
+                    expand.update(&block.0);
+                }
+                expand.update(info).update(&[index as u8 + 1]);
+                // The previous block is wiped as it is replaced
+                block = expand.finalize();
+                chunk.copy_from_slice(&block.0[..chunk.len()]);
+            });
+
+        subkey
+    }
+}
+
+// The caller still owns `value` and is responsible for wiping it
+impl<const N: usize> TryFrom<&[u8]> for ZeroizeArray<N> {
+    type Error = SecureMemError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != N {
+            return Err(SecureMemError::LengthMismatch {
+                expected: N,
+                actual: value.len(),
+            });
+        }
+
+        let mut array = ZeroizeArray::zeroed();
+        array.0.copy_from_slice(value);
+
+        Ok(array)
+    }
+}
+
+// The vector is wiped up to its capacity whether or not the length matched
+impl<const N: usize> TryFrom<Vec<u8>> for ZeroizeArray<N> {
+    type Error = SecureMemError;
+
+    fn try_from(mut value: Vec<u8>) -> Result<Self, Self::Error> {
+        let outcome = ZeroizeArray::try_from(&value[..]);
+        value.zeroize();
+
+        outcome
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for ZeroizeArray<N> {
+    fn from(value: [u8; N]) -> Self {
+        ZeroizeArray(value)
+    }
+}
+
+impl<const N: usize> Default for ZeroizeArray<N> {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl<const N: usize> Clone for ZeroizeArray<N> {
+    fn clone(&self) -> Self {
+        ZeroizeArray(self.0)
+    }
+}
+
+// `&a ^ &b` leaves both inputs as they are, the xor is computed in the array that is returned
+impl<const N: usize> core::ops::BitXor<&ZeroizeArray<N>> for &ZeroizeArray<N> {
+    type Output = ZeroizeArray<N>;
+
+    fn bitxor(self, other: &ZeroizeArray<N>) -> ZeroizeArray<N> {
+        let mut mixed = self.clone();
+        mixed.xor_in_place(other);
+
+        mixed
+    }
+}
+
+impl<const N: usize> ConstantTimeEq for ZeroizeArray<N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl<const N: usize> PartialEq for ZeroizeArray<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const N: usize> Eq for ZeroizeArray<N> {}
+
+impl ZeroizeArray<32> {
+    /// Only available on 32 byte arrays, any other length is rejected at compile time
+    ///
+    /// ```compile_fail
+    /// let short = xor_securemem::ZeroizeArray::<16>::zeroed();
+    /// short.chacha_key();
+    /// ```
+    pub fn chacha_key(&self) -> &Key {
+        Key::from_slice(self.0.as_slice())
+    }
+}
+
+impl<const N: usize> Zeroize for ZeroizeArray<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<const N: usize> Drop for ZeroizeArray<N> {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl<const N: usize> ZeroizeOnDrop for ZeroizeArray<N> {}
+
+// Deliberately never prints the contents, so logging or `dbg!` of a secret cannot leak it
+impl<const N: usize> core::fmt::Debug for ZeroizeArray<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ZeroizeArray").field(&Redacted(N)).finish()
+    }
+}
+
+pub struct ZeroizeBytesArray<const N: usize>(BytesMut);
+
+impl<const N: usize> ZeroizeBytesArray<N> {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+
+    // Holds no bytes yet, with room for exactly `N`
+    pub fn empty() -> Self {
+        ZeroizeBytesArray(BytesMut::with_capacity(N))
+    }
+
+    // Reserves `capacity` bytes up front, but never less than `N`, so growing it later does not leave stale copies
+    pub fn with_capacity(capacity: usize) -> Self {
+        ZeroizeBytesArray(BytesMut::with_capacity(capacity.max(N)))
+    }
+
+    /// Replaces the contents with `value`, wiping the previous bytes and the consumed `value` buffer
+    pub fn set(&mut self, mut value: BytesMut) -> &mut Self {
+        wipe_bytes_mut(&mut self.0);
+        self.0.put(&value[..]);
+        wipe_bytes_mut(&mut value);
+
+        self
+    }
+
+    /// Appends `value` after the existing contents, wiping the consumed `value` buffer
+    pub fn append(&mut self, mut value: BytesMut) -> &mut Self {
+        reserve_wiping(&mut self.0, value.len());
+        self.0.put(&value[..]);
+        wipe_bytes_mut(&mut value);
+
+        self
+    }
+
+    #[deprecated(note = "use `with_capacity`, which takes the total capacity")]
+    pub fn with_additional_capacity(capacity: usize) -> Self {
+        ZeroizeBytesArray(BytesMut::with_capacity(N + capacity))
+    }
+
+    // Only the allocation made here is locked, growing past `N` bytes moves the contents into unlocked memory
+    #[cfg(feature = "mlock")]
+    pub fn try_new_locked() -> Result<Self, SecureMemError> {
+        let bytes = BytesMut::with_capacity(N);
+        crate::mlock::lock(bytes.as_ptr(), bytes.capacity())?;
+
+        Ok(ZeroizeBytesArray(bytes))
+    }
+
+    // Wipes the bytes past `len` before dropping them, a `len` at or past the current length does nothing
+    pub fn truncate(&mut self, len: usize) -> &mut Self {
+        truncate_wiping(&mut self.0, len);
+
+        self
+    }
+
+    // Constant time, every byte is folded in whether or not an earlier one was non-zero
+    pub fn ct_is_zero(&self) -> Choice {
+        ct_is_zero(&self.0[..])
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    pub fn expose(&self) -> &BytesMut {
+        &self.0
+    }
+
+    pub fn try_chacha_key(&self) -> Result<&Key, SecureMemError> {
+        try_chacha_key(&self.0)
+    }
+
+    // Panics when the operating system RNG fails
+    #[cfg(feature = "getrandom")]
+    pub fn csprng() -> Self {
+        Self::try_csprng().expect(OS_RNG_FAILED)
+    }
+
+    // Anything written before a failure is wiped when the partially filled buffer drops
+    #[cfg(feature = "getrandom")]
+    pub fn try_csprng() -> Result<Self, SecureMemError> {
+        let mut csprng = ZeroizeBytesArray(BytesMut::zeroed(N));
+        try_fill_random(&mut OsRng, &mut csprng.0[..])?;
+
+        Ok(csprng)
+    }
+}
+
+impl<const N: usize> Default for ZeroizeBytesArray<N> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+// A deep copy into a fresh allocation of the same capacity, the backing storage is never shared and a locked
+// original does not make the copy locked
+impl<const N: usize> Clone for ZeroizeBytesArray<N> {
+    fn clone(&self) -> Self {
+        ZeroizeBytesArray(deep_copy(&self.0))
+    }
+}
+
+impl<const N: usize> ConstantTimeEq for ZeroizeBytesArray<N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl<const N: usize> PartialEq for ZeroizeBytesArray<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const N: usize> Eq for ZeroizeBytesArray<N> {}
+
+impl<const N: usize> Zeroize for ZeroizeBytesArray<N> {
+    fn zeroize(&mut self) {
+        wipe_bytes_mut(&mut self.0)
+    }
+}
+
+impl<const N: usize> Drop for ZeroizeBytesArray<N> {
+    fn drop(&mut self) {
+        self.zeroize();
+
+        #[cfg(feature = "mlock")]
+        crate::mlock::unlock(self.0.as_ptr());
+    }
+}
+
+impl<const N: usize> ZeroizeOnDrop for ZeroizeBytesArray<N> {}
+
+impl<const N: usize> core::fmt::Debug for ZeroizeBytesArray<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ZeroizeBytesArray")
+            .field(&Redacted(self.0.len()))
+            .finish()
+    }
+}
+
+pub struct ZeroizeBytes(pub(crate) BytesMut);
+
+impl ZeroizeBytes {
+    pub fn new() -> Self {
+        Self::empty()
+    }
+
+    // Holds no bytes and owns no allocation until the first `set` or `append`
+    pub fn empty() -> Self {
+        ZeroizeBytes(BytesMut::new())
+    }
+
+    // Reserve the final length up front when it is known, growing the buffer later leaves stale copies behind
+    pub fn with_capacity(capacity: usize) -> Self {
+        ZeroizeBytes(BytesMut::with_capacity(capacity))
+    }
+
+    /// Replaces the contents with `value`, wiping the previous bytes and the consumed `value` buffer
+    pub fn set(&mut self, mut value: BytesMut) -> &mut Self {
+        wipe_bytes_mut(&mut self.0);
+        self.0.put(&value[..]);
+        wipe_bytes_mut(&mut value);
+
+        self
+    }
+
+    /// Appends `value` after the existing contents, wiping the consumed `value` buffer
+    pub fn append(&mut self, mut value: BytesMut) -> &mut Self {
+        reserve_wiping(&mut self.0, value.len());
+        self.0.put(&value[..]);
+        wipe_bytes_mut(&mut value);
+
+        self
+    }
+
+    #[deprecated(note = "use `with_capacity`")]
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        Self::with_capacity(capacity)
+    }
+
+    // Only the allocation made here is locked, growing past `capacity` moves the contents into unlocked memory
+    #[cfg(feature = "mlock")]
+    pub fn try_with_capacity_locked(capacity: usize) -> Result<Self, SecureMemError> {
+        let bytes = BytesMut::with_capacity(capacity);
+        crate::mlock::lock(bytes.as_ptr(), bytes.capacity())?;
+
+        Ok(ZeroizeBytes(bytes))
+    }
+
+    // Copies into a fresh buffer of exactly the right size, then wipes the whole original allocation
+    pub fn from_vec(mut value: Vec<u8>) -> Self {
+        let mut bytes = BytesMut::with_capacity(value.len());
+        bytes.extend_from_slice(&value);
+        value.zeroize();
+
+        ZeroizeBytes(bytes)
+    }
+
+    // Wipes the bytes past `len` before dropping them, a `len` at or past the current length does nothing
+    pub fn truncate(&mut self, len: usize) -> &mut Self {
+        truncate_wiping(&mut self.0, len);
+
+        self
+    }
+
+    // Growing past the capacity moves the contents into a new allocation and wipes the old one first, a `len` no
+    // allocation could hold does nothing
+    pub fn resize(&mut self, len: usize, value: u8) -> &mut Self {
+        if len <= self.0.len() {
+            return self.truncate(len);
+        }
+        if len > isize::MAX as usize {
+            return self;
+        }
+
+        let additional = len - self.0.len();
+        reserve_wiping(&mut self.0, additional);
+        self.0.resize(len, value);
+
+        self
+    }
+
+    // Exchanges the two buffers rather than their bytes, so lengths may differ and nothing is copied
+    pub fn swap(&mut self, other: &mut ZeroizeBytes) -> &mut Self {
+        core::mem::swap(&mut self.0, &mut other.0);
+
+        self
+    }
+
+    pub fn xor_in_place(&mut self, other: &[u8]) -> Result<&mut Self, SecureMemError> {
+        if other.len() != self.0.len() {
+            return Err(SecureMemError::LengthMismatch {
+                expected: self.0.len(),
+                actual: other.len(),
+            });
+        }
+
+        self.0
+            .iter_mut()
+            .zip(other)
+            .for_each(|(byte, mask)| *byte ^= mask);
+
+        Ok(self)
+    }
+
+    // Constant time, every byte is folded in whether or not an earlier one was non-zero
+    pub fn ct_is_zero(&self) -> Choice {
+        ct_is_zero(&self.0[..])
+    }
+
+    // For checking against untrusted input such as a received tag. Only the lengths are compared up front, the
+    // bytes themselves are always compared in full.
+    pub fn ct_eq_slice(&self, other: &[u8]) -> bool {
+        self.0[..].ct_eq(other).into()
+    }
+
+    // Same digest as `ZeroizeArray::fingerprint`, so equal secrets fingerprint equally in either container
+    pub fn fingerprint(&self) -> [u8; 32] {
+        fingerprint(&self.0[..])
+    }
+
+    pub fn fingerprint_hex(&self) -> String {
+        fingerprint_hex(&self.0[..])
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    pub fn expose(&self) -> &BytesMut {
+        &self.0
+    }
+
+    pub fn try_chacha_key(&self) -> Result<&Key, SecureMemError> {
+        try_chacha_key(&self.0)
+    }
+
+    // Panics when the operating system RNG fails
+    #[cfg(feature = "getrandom")]
+    pub fn csprng<const BUFFER_SIZE: usize>() -> Self {
+        Self::try_csprng::<BUFFER_SIZE>().expect(OS_RNG_FAILED)
+    }
+
+    // Anything written before a failure is wiped when the partially filled buffer drops
+    #[cfg(feature = "getrandom")]
+    pub fn try_csprng<const BUFFER_SIZE: usize>() -> Result<Self, SecureMemError> {
+        let mut csprng = ZeroizeBytes(BytesMut::zeroed(BUFFER_SIZE));
+        try_fill_random(&mut OsRng, &mut csprng.0[..])?;
+
+        Ok(csprng)
+    }
+}
+
+impl Default for ZeroizeBytes {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Clone for ZeroizeBytes {
+    fn clone(&self) -> Self {
+        ZeroizeBytes(deep_copy(&self.0))
+    }
+}
+
+// Buffers of different lengths compare unequal straight away, only the lengths leak and never the position of a
+// differing byte
+impl ConstantTimeEq for ZeroizeBytes {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl PartialEq for ZeroizeBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ZeroizeBytes {}
+
+impl Zeroize for ZeroizeBytes {
+    fn zeroize(&mut self) {
+        wipe_bytes_mut(&mut self.0)
+    }
+}
+
+impl Drop for ZeroizeBytes {
+    fn drop(&mut self) {
+        self.zeroize();
+
+        #[cfg(feature = "mlock")]
+        crate::mlock::unlock(self.0.as_ptr());
+    }
+}
+
+impl ZeroizeOnDrop for ZeroizeBytes {}
+
+impl core::fmt::Debug for ZeroizeBytes {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ZeroizeBytes")
+            .field(&Redacted(self.0.len()))
+            .finish()
+    }
+}
+
+// Every conversion consumes its source and wipes it, so moving a secret between the fixed size and the growable
+// containers never leaves a stale copy on either side
+impl<const N: usize> From<ZeroizeArray<N>> for ZeroizeBytesArray<N> {
+    fn from(mut value: ZeroizeArray<N>) -> Self {
+        ZeroizeBytesArray(take_array(&mut value))
+    }
+}
+
+impl<const N: usize> From<ZeroizeArray<N>> for ZeroizeBytes {
+    fn from(mut value: ZeroizeArray<N>) -> Self {
+        ZeroizeBytes(take_array(&mut value))
+    }
+}
+
+impl<const N: usize> TryFrom<ZeroizeBytesArray<N>> for ZeroizeArray<N> {
+    type Error = SecureMemError;
+
+    fn try_from(mut value: ZeroizeBytesArray<N>) -> Result<Self, Self::Error> {
+        take_exact(&mut value.0)
+    }
+}
+
+impl<const N: usize> TryFrom<ZeroizeBytes> for ZeroizeArray<N> {
+    type Error = SecureMemError;
+
+    fn try_from(mut value: ZeroizeBytes) -> Result<Self, Self::Error> {
+        take_exact(&mut value.0)
+    }
+}
+
+fn fingerprint(bytes: &[u8]) -> [u8; 32] {
+    blake3::derive_key(FINGERPRINT_CONTEXT, bytes)
+}
+
+fn fingerprint_hex(bytes: &[u8]) -> String {
+    format!("{:?}", LowerHex(&fingerprint(bytes)[..8]))
+}
+
+pub(crate) fn ct_is_zero(bytes: &[u8]) -> Choice {
+    bytes
+        .iter()
+        .fold(0u8, |folded, byte| folded | byte)
+        .ct_eq(&0)
+}
+
+fn truncate_wiping(bytes: &mut BytesMut, len: usize) {
+    if len < bytes.len() {
+        bytes[len..].zeroize();
+        bytes.truncate(len);
+    }
+}
+
+fn take_array<const N: usize>(array: &mut ZeroizeArray<N>) -> BytesMut {
+    let mut bytes = BytesMut::with_capacity(N);
+    bytes.extend_from_slice(&array.0);
+    array.zeroize();
+
+    bytes
+}
+
+fn take_exact<const N: usize>(bytes: &mut BytesMut) -> Result<ZeroizeArray<N>, SecureMemError> {
+    let outcome = ZeroizeArray::try_from(&bytes[..]);
+    wipe_bytes_mut(bytes);
+
+    outcome
+}
+
+// Stands in for secret bytes in `Debug` output, only the length is shown
+struct Redacted(usize);
+
+impl core::fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[REDACTED; {}]", self.0)
+    }
+}
+
+struct LowerHex<'a>(&'a [u8]);
+
+impl core::fmt::Debug for LowerHex<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+fn deep_copy(bytes: &BytesMut) -> BytesMut {
+    let mut copy = BytesMut::with_capacity(bytes.capacity());
+    copy.extend_from_slice(&bytes[..]);
+
+    copy
+}
+
+fn try_chacha_key(bytes: &BytesMut) -> Result<&Key, SecureMemError> {
+    if bytes.len() != 32 {
+        return Err(SecureMemError::InvalidLength);
+    }
+
+    Ok(Key::from_slice(&bytes[..]))
+}
+
+// Panics when the operating system RNG fails
+#[cfg(feature = "getrandom")]
+fn random_nonce<C: Cipher>() -> Nonce<C> {
+    try_nonce_from_rng(&mut OsRng).expect(OS_RNG_FAILED)
+}
+
+pub(crate) fn try_nonce_from_rng<S: ArrayLength<u8>, R: RngCore + CryptoRng>(
+    rng: &mut R,
+) -> Result<GenericArray<u8, S>, SecureMemError> {
+    let mut nonce = GenericArray::default();
+    try_fill_random(rng, nonce.as_mut_slice())?;
+
+    Ok(nonce)
+}
+
+pub(crate) fn try_fill_random<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    buffer: &mut [u8],
+) -> Result<(), SecureMemError> {
+    rng.try_fill_bytes(buffer)
+        .map_err(|_| SecureMemError::RandomnessUnavailable)
+}
+
+// Binds the key to the nonce with a domain separated BLAKE3 derivation, which is one-way so the key cannot be
+// recovered from it, and changes with every nonce so ciphertexts under the same key cannot be linked
+pub(crate) fn compute_key_commitment<C: Cipher>(
+    key: &Key,
+    nonce: &Nonce<C>,
+) -> [u8; KEY_COMMITMENT_LENGTH] {
+    let mut hasher = blake3::Hasher::new_derive_key(KEY_COMMITMENT_CONTEXT);
+    hasher.update(key.as_slice());
+    hasher.update(nonce.as_slice());
+
+    *hasher.finalize().as_bytes()
+}
+
+// Reads the `Cipher::ALGORITHM` a blob from `EncryptedMem::to_bytes` was sealed with, so a reader can pick the
+// matching `EncryptedMem` type before parsing it
+pub fn wire_algorithm(data: &[u8]) -> Result<u8, SecureMemError> {
+    if data.len() < WIRE_HEADER_LENGTH {
+        return Err(SecureMemError::InvalidLength);
+    }
+    if data[..WIRE_MAGIC.len()] != WIRE_MAGIC {
+        return Err(SecureMemError::InvalidHeader);
+    }
+
+    Ok(data[WIRE_HEADER_LENGTH - 1])
+}
+
+// `BytesMut::clear` only resets the length, so the whole allocation is volatile-zeroed once
+// the length is dropped to zero and every byte up to the capacity is spare capacity.
+pub(crate) fn wipe_bytes_mut(bytes: &mut BytesMut) {
+    bytes.clear();
+    bytes.spare_capacity_mut().zeroize();
+}
+
+// Growing a `BytesMut` frees the old allocation without wiping it, so when `additional` bytes do not fit the contents
+// are copied into a fresh allocation here and the old one is wiped before it drops
+pub(crate) fn reserve_wiping(bytes: &mut BytesMut, additional: usize) {
+    if bytes.capacity() - bytes.len() >= additional {
+        return;
+    }
+
+    let mut relocated = BytesMut::with_capacity(bytes.len() + additional);
+    relocated.extend_from_slice(bytes);
+    let mut previous = core::mem::replace(bytes, relocated);
+    wipe_bytes_mut(&mut previous);
+}
+
+#[cfg(all(test, feature = "getrandom"))]
+mod store_tests {
+    use super::BytesMut;
+    use crate::*;
+    use zeroize::Zeroize;
+
+    const TAMPERED: SecureMemError = match cfg!(feature = "key-commitment") {
+        true => SecureMemError::Corrupted,
+        false => SecureMemError::AuthenticationFailed,
+    };
+
+    #[test]
+    fn flipped_ciphertext_byte_fails_authentication() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([7u8; 32]), key.chacha_key())
+            .unwrap();
+
+        store.ciphertext.0[0] ^= 0x01;
+
+        assert_eq!(store.decrypt(key.chacha_key()).err(), Some(TAMPERED));
+    }
+
+    #[test]
+    fn flipped_tag_byte_fails_authentication() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([7u8; 32]), key.chacha_key())
+            .unwrap();
+
+        store.ciphertext.0[32 + TAG_LENGTH - 1] ^= 0x80;
+
+        assert_eq!(store.decrypt(key.chacha_key()).err(), Some(TAMPERED));
+    }
+
+    #[test]
+    fn encrypt_draws_a_fresh_nonce_every_call() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+
+        store
+            .encrypt(&ZeroizeArray::new([1u8; 32]), key.chacha_key())
+            .unwrap();
+        let first = EncryptedMem::<32> {
+            ciphertext: store.ciphertext.clone(),
+            nonce: store.nonce,
+            key_commitment: store.key_commitment,
+        };
+
+        store
+            .encrypt(&ZeroizeArray::new([2u8; 32]), key.chacha_key())
+            .unwrap();
+
+        assert_ne!(first.nonce, store.nonce);
+        assert_eq!(
+            first.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[1u8; 32]
+        );
+        assert_eq!(
+            store.decrypt(key.chacha_key()).unwrap().expose_borrowed(),
+            &[2u8; 32]
+        );
+    }
+
+    #[test]
+    fn buffers_are_sized_for_exactly_one_tag() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<4096, XChaCha20Poly1305>::new();
+        assert_eq!(
+            store.ciphertext.0.capacity(),
+            4096 + EncryptedMem::<4096, XChaCha20Poly1305>::TAG_LENGTH
+        );
+
+        store
+            .encrypt(&ZeroizeArray::new([7u8; 4096]), key.chacha_key())
+            .unwrap();
+
+        // Equal length and capacity means the tag was appended without growing the buffer
+        assert_eq!(store.ciphertext.0.len(), store.ciphertext.0.capacity());
+    }
+
+    #[test]
+    fn encrypt_moves_scratch_buffer_into_ciphertext() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+
+        store
+            .encrypt(&ZeroizeArray::new([3u8; 32]), key.chacha_key())
+            .unwrap();
+
+        assert_eq!(store.ciphertext.0.len(), 32 + TAG_LENGTH);
+        assert_eq!(store.ciphertext.0.capacity(), 32 + TAG_LENGTH);
+    }
+
+    #[test]
+    fn zeroize_encrypted_mem_wipes_ciphertext_and_nonce() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([5u8; 32]), key.chacha_key())
+            .unwrap();
+
+        store.zeroize();
+
+        assert!(store.ciphertext.0.is_empty());
+        assert!(backing_storage(&store.ciphertext.0)
+            .iter()
+            .all(|byte| *byte == 0));
+        assert!(store.nonce.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn set_replaces_and_wipes_displaced_bytes() {
+        let mut secret = ZeroizeBytes::with_capacity(64);
+        secret.set(BytesMut::from(&[0xAAu8; 48][..]));
+        secret.set(BytesMut::from(&[0x11u8; 8][..]));
+
+        assert_eq!(&secret.0[..], &[0x11u8; 8]);
+        assert!(backing_storage(&secret.0)[8..]
+            .iter()
+            .all(|byte| *byte == 0));
+
+        let mut array = ZeroizeBytesArray::<16>::new();
+        array.set(BytesMut::from(&[0xBBu8; 16][..]));
+        array.set(BytesMut::from(&[0x22u8; 4][..]));
+
+        assert_eq!(&array.0[..], &[0x22u8; 4]);
+        assert!(backing_storage(&array.0)[4..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn append_concatenates() {
+        let mut secret = ZeroizeBytes::new();
+        secret.set(BytesMut::from(&[1u8, 2][..]));
+        secret.append(BytesMut::from(&[3u8][..]));
+
+        assert_eq!(&secret.0[..], &[1u8, 2, 3]);
+
+        let mut array = ZeroizeBytesArray::<4>::new();
+        array.append(BytesMut::from(&[4u8, 5][..]));
+        array.append(BytesMut::from(&[6u8][..]));
+
+        assert_eq!(&array.0[..], &[4u8, 5, 6]);
+    }
+
+    #[test]
+    fn append_past_capacity_moves_to_a_larger_buffer() {
+        let mut secret = ZeroizeBytes::with_capacity(2);
+        secret.set(BytesMut::from(&[1u8, 2][..]));
+        let before = secret.0.as_ptr();
+        secret.append(BytesMut::from(&[3u8, 4, 5][..]));
+
+        assert_eq!(&secret.0[..], &[1u8, 2, 3, 4, 5]);
+        assert!(secret.0.capacity() >= 5);
+        assert_ne!(secret.0.as_ptr(), before);
+
+        let mut array = ZeroizeBytesArray::<2>::new();
+        array.append(BytesMut::from(&[6u8, 7][..]));
+        array.append(BytesMut::from(&[8u8][..]));
+        assert_eq!(&array.0[..], &[6u8, 7, 8]);
+
+        // Appending within the spare capacity keeps the allocation
+        let mut roomy = ZeroizeBytes::with_capacity(8);
+        roomy.append(BytesMut::from(&[1u8][..]));
+        let before = roomy.0.as_ptr();
+        roomy.append(BytesMut::from(&[2u8][..]));
+        assert_eq!(roomy.0.as_ptr(), before);
+    }
+
+    // A partitioning attack needs one ciphertext that authenticates under two keys. Poly1305 alone cannot prevent
+    // that, so this stands in an AEAD-valid ciphertext for the second key and checks the commitment to the first
+    // still rejects it: at most one key can ever open a committed blob.
+    #[test]
+    fn committed_ciphertext_only_opens_under_the_committed_key() {
+        let committed_key = ZeroizeArray::<32>::csprng();
+        let other_key = ZeroizeArray::<32>::csprng();
+        let mut valid_for_other = EncryptedMem::<32>::new();
+        valid_for_other
+            .encrypt(&ZeroizeArray::new([8u8; 32]), other_key.chacha_key())
+            .unwrap();
+
+        let nonce = *valid_for_other.nonce();
+        let commitment =
+            super::compute_key_commitment::<XChaCha8Poly1305>(committed_key.chacha_key(), &nonce);
+        let crafted = EncryptedMem::<32>::from_parts_with_key_commitment(
+            valid_for_other.ciphertext().clone(),
+            nonce,
+            commitment,
+        );
+
+        assert_eq!(
+            crafted.decrypt(other_key.chacha_key()).err(),
+            Some(SecureMemError::WrongKey)
+        );
+        assert_eq!(
+            crafted.decrypt(committed_key.chacha_key()).err(),
+            Some(SecureMemError::Corrupted)
+        );
+        assert!(
+            EncryptedMem::<32>::from_parts(valid_for_other.ciphertext().clone(), nonce)
+                .decrypt(other_key.chacha_key())
+                .is_ok()
+        );
+    }
+
+    #[cfg(feature = "key-commitment")]
+    #[test]
+    fn key_commitment_distinguishes_wrong_key_from_corruption() {
+        let key = ZeroizeArray::<32>::csprng();
+        let wrong_key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([7u8; 32]), key.chacha_key())
+            .unwrap();
+
+        assert!(store.key_commitment().is_some());
+        assert_eq!(
+            store.decrypt(wrong_key.chacha_key()).err(),
+            Some(SecureMemError::WrongKey)
+        );
+
+        store.ciphertext.0[0] ^= 0x01;
+        assert_eq!(
+            store.decrypt(key.chacha_key()).err(),
+            Some(SecureMemError::Corrupted)
+        );
+        assert_eq!(
+            store.decrypt(wrong_key.chacha_key()).err(),
+            Some(SecureMemError::WrongKey)
+        );
+    }
+
+    #[cfg(feature = "key-commitment")]
+    #[test]
+    fn key_commitment_survives_the_wire_format() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([9u8; 32]), key.chacha_key())
+            .unwrap();
+
+        let bytes = store.to_bytes();
+        assert_eq!(bytes.0[WIRE_MAGIC.len()], WIRE_VERSION_KEY_COMMITTED);
+
+        let restored = EncryptedMem::<32>::from_bytes(&bytes.0[..]).unwrap();
+        assert_eq!(restored.key_commitment(), store.key_commitment());
+        assert_eq!(
+            restored.decrypt(key.chacha_key()).unwrap().expose(),
+            [9u8; 32]
+        );
+        assert_eq!(
+            EncryptedMem::<32>::from_bytes(&bytes.0[..bytes.0.len() - 1]).err(),
+            Some(SecureMemError::InvalidLength)
+        );
+    }
+
+    #[cfg(feature = "mlock")]
+    #[test]
+    fn locked_buffers_are_unlocked_on_drop() {
+        let mut array = ZeroizeBytesArray::<64>::try_new_locked().unwrap();
+        let mut bytes = ZeroizeBytes::try_with_capacity_locked(128).unwrap();
+        let array_ptr = array.0.as_ptr();
+        let bytes_ptr = bytes.0.as_ptr();
+
+        assert!(crate::mlock::is_locked(array_ptr));
+        assert!(crate::mlock::is_locked(bytes_ptr));
+
+        array.set(BytesMut::from(&[1u8; 64][..]));
+        bytes.set(BytesMut::from(&[2u8; 128][..]));
+        drop(array);
+        drop(bytes);
+
+        assert!(!crate::mlock::is_locked(array_ptr));
+        assert!(!crate::mlock::is_locked(bytes_ptr));
+    }
+
+    #[test]
+    fn decrypt_with_wipes_scratch_when_the_closure_panics() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut store = EncryptedMem::<32>::new();
+        store
+            .encrypt(&ZeroizeArray::new([0x66u8; 32]), key.chacha_key())
+            .unwrap();
+
+        let mut scratch = ZeroizeArray::<32>::zeroed();
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            store.decrypt_with_scratch(key.chacha_key(), &mut scratch, |plaintext| {
+                assert_eq!(plaintext, &[0x66u8; 32]);
+                panic!("closure panicked while holding the plaintext");
+            })
+        }));
+
+        assert!(outcome.is_err());
+        assert_eq!(scratch.0, [0u8; 32]);
+
+        let sum = store
+            .decrypt_with_scratch(key.chacha_key(), &mut scratch, |plaintext| {
+                plaintext.iter().map(|byte| *byte as usize).sum::<usize>()
+            })
+            .unwrap();
+        assert_eq!(sum, 0x66 * 32);
+        assert_eq!(scratch.0, [0u8; 32]);
+    }
+
+    #[test]
+    fn conversions_wipe_their_source() {
+        let mut array = ZeroizeArray::new([0x77u8; 16]);
+        let bytes = super::take_array(&mut array);
+        assert_eq!(array.0, [0u8; 16]);
+        assert_eq!(&bytes[..], &[0x77u8; 16]);
+
+        let mut growable = BytesMut::from(&[0x78u8; 16][..]);
+        let array = super::take_exact::<16>(&mut growable).unwrap();
+        assert_eq!(array.0, [0x78u8; 16]);
+        assert!(growable.is_empty());
+        assert!(backing_storage(&growable).iter().all(|byte| *byte == 0));
+
+        let mut growable = BytesMut::from(&[0x79u8; 15][..]);
+        assert_eq!(
+            super::take_exact::<16>(&mut growable).err(),
+            Some(SecureMemError::LengthMismatch {
+                expected: 16,
+                actual: 15
+            })
+        );
+        assert!(backing_storage(&growable).iter().all(|byte| *byte == 0));
+
+        let round_trip = ZeroizeBytes::from(ZeroizeArray::new([0x7Au8; 8]));
+        let array: ZeroizeArray<8> = round_trip.try_into().unwrap();
+        let fixed = ZeroizeBytesArray::from(array);
+        assert_eq!(&fixed.0[..], &[0x7Au8; 8]);
+        assert!(ZeroizeArray::try_from(fixed).is_ok());
+        assert!(ZeroizeArray::try_from(ZeroizeBytesArray::<8>::new()).is_err());
+    }
+
+    #[test]
+    fn truncate_and_resize_wipe_what_they_drop() {
+        let mut secret = ZeroizeBytes::with_capacity(8);
+        secret.set(BytesMut::from(&[0xEEu8; 8][..]));
+
+        secret.truncate(3).truncate(8);
+        assert_eq!(&secret.0[..], &[0xEEu8; 3]);
+        assert!(backing_storage(&secret.0)[3..]
+            .iter()
+            .all(|byte| *byte == 0));
+
+        secret.resize(6, 0);
+        assert_eq!(&secret.0[..], &[0xEE, 0xEE, 0xEE, 0, 0, 0]);
+        secret.resize(64, 1).resize(usize::MAX, 0);
+        assert_eq!(secret.len(), 64);
+        assert_eq!(&secret.0[..4], &[0xEE, 0xEE, 0xEE, 0]);
+        assert!(secret.0[6..].iter().all(|byte| *byte == 1));
+        secret.resize(2, 0);
+        assert!(backing_storage(&secret.0)[2..]
+            .iter()
+            .all(|byte| *byte == 0));
+
+        let mut array = ZeroizeBytesArray::<32>::new();
+        array.set(BytesMut::from(&[0xDDu8; 32][..]));
+        array.truncate(30);
+        assert_eq!(array.len(), 30);
+        assert!(backing_storage(&array.0)[30..]
+            .iter()
+            .all(|byte| *byte == 0));
+    }
+
+    fn backing_storage(bytes: &BytesMut) -> &[u8] {
+        // The allocation stays alive after `zeroize` and every byte is initialized by then
+        unsafe { core::slice::from_raw_parts(bytes.as_ptr(), bytes.capacity()) }
+    }
+
+    #[test]
+    fn zeroize_bytes_array_wipes_backing_storage() {
+        let mut secret = ZeroizeBytesArray::<32>::with_capacity(32 + TAG_LENGTH);
+        secret.set(BytesMut::from(&[0xAAu8; 48][..]));
+
+        let capacity = secret.0.capacity();
+        secret.zeroize();
+
+        assert_eq!(secret.0.capacity(), capacity);
+        assert!(backing_storage(&secret.0).iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn zeroize_bytes_wipes_backing_storage() {
+        let mut secret = ZeroizeBytes::with_capacity(64);
+        secret.set(BytesMut::from(&[0x55u8; 40][..]));
+
+        let capacity = secret.0.capacity();
+        secret.zeroize();
+
+        assert_eq!(secret.0.capacity(), capacity);
+        assert!(backing_storage(&secret.0).iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn encrypted_string_rejects_invalid_utf8() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut invalid = ZeroizeBytes::new();
+        invalid.set(BytesMut::from(&[0xC3u8, 0x28][..]));
+
+        let mut bytes = EncryptedBytes::<XChaCha8Poly1305>::new();
+        bytes.encrypt(&invalid, key.chacha_key()).unwrap();
+        let invalid = super::EncryptedString(bytes);
+
+        assert_eq!(
+            invalid.with_str(key.chacha_key(), |_| ()),
+            Err(SecureMemError::InvalidString)
+        );
+        assert_eq!(
+            invalid.decrypt(key.chacha_key()).err().map(|_| ()),
+            Some(())
+        );
+    }
+}
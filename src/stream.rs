@@ -0,0 +1,154 @@
+use crate::{store::wipe_bytes_mut, Cipher, SecureMemError, XChaCha8Poly1305, ZeroizeBytes};
+use chacha20poly1305::{
+    aead::{bytes::BytesMut, generic_array::typenum::Unsigned, AeadCore, Nonce},
+    Key,
+};
+
+// Big endian chunk counter followed by the last chunk flag, as in the STREAM construction
+const STREAM_NONCE_OVERHEAD: usize = 5;
+
+// Encrypts data too large for a single `EncryptedMem` chunk by chunk. Every chunk is authenticated under a nonce
+// derived from a random prefix, its position and a last chunk flag, so chunks cannot be reordered, dropped or
+// appended after `finalize`. The encrypting and the decrypting side each keep their own `EncryptedStream`.
+pub struct EncryptedStream<C: Cipher = XChaCha8Poly1305> {
+    cipher: C,
+    nonce: Nonce<C>,
+    position: u32,
+}
+
+impl<C: Cipher> EncryptedStream<C> {
+    pub const NONCE_PREFIX_LENGTH: usize =
+        <C as AeadCore>::NonceSize::USIZE - STREAM_NONCE_OVERHEAD;
+    pub const TAG_LENGTH: usize = <C as AeadCore>::TagSize::USIZE;
+
+    // Starts a new stream for encryption with a random nonce prefix
+    #[cfg(feature = "getrandom")]
+    pub fn new(key: &Key) -> Result<Self, SecureMemError> {
+        let mut nonce = Nonce::<C>::default();
+        crate::store::try_fill_random(
+            &mut rand_core::OsRng,
+            &mut nonce[..Self::NONCE_PREFIX_LENGTH],
+        )?;
+
+        Ok(EncryptedStream {
+            cipher: C::new(key),
+            nonce,
+            position: 0,
+        })
+    }
+
+    // Resumes a stream for decryption with the prefix the encrypting side sent along
+    pub fn from_nonce_prefix(key: &Key, nonce_prefix: &[u8]) -> Result<Self, SecureMemError> {
+        if nonce_prefix.len() != Self::NONCE_PREFIX_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        let mut nonce = Nonce::<C>::default();
+        nonce[..Self::NONCE_PREFIX_LENGTH].copy_from_slice(nonce_prefix);
+
+        Ok(EncryptedStream {
+            cipher: C::new(key),
+            nonce,
+            position: 0,
+        })
+    }
+
+    pub fn nonce_prefix(&self) -> &[u8] {
+        &self.nonce[..Self::NONCE_PREFIX_LENGTH]
+    }
+
+    pub fn encrypt_chunk(&mut self, chunk: &[u8]) -> Result<ZeroizeBytes, SecureMemError> {
+        let ciphertext = self.seal(chunk, false)?;
+        self.position += 1;
+
+        Ok(ciphertext)
+    }
+
+    // Encrypts the last chunk, consuming the stream so nothing can be appended after it
+    pub fn finalize(self, last_chunk: &[u8]) -> Result<ZeroizeBytes, SecureMemError> {
+        self.seal(last_chunk, true)
+    }
+
+    pub fn decrypt_chunk(&mut self, chunk: &[u8]) -> Result<ZeroizeBytes, SecureMemError> {
+        let plaintext = self.open(chunk, false)?;
+        self.position += 1;
+
+        Ok(plaintext)
+    }
+
+    // Only succeeds on the chunk that was passed to `finalize`, which is how a truncated stream is detected
+    pub fn finalize_decrypt(self, last_chunk: &[u8]) -> Result<ZeroizeBytes, SecureMemError> {
+        self.open(last_chunk, true)
+    }
+
+    fn chunk_nonce(&self, last_chunk: bool) -> Result<Nonce<C>, SecureMemError> {
+        // Running out of positions would reuse a nonce, only the last chunk may take the final position
+        if self.position == u32::MAX && !last_chunk {
+            return Err(SecureMemError::CounterExhausted);
+        }
+
+        let mut nonce = self.nonce.clone();
+        let (counter, flag) = nonce[Self::NONCE_PREFIX_LENGTH..].split_at_mut(4);
+        counter.copy_from_slice(&self.position.to_be_bytes());
+        flag[0] = last_chunk as u8;
+
+        Ok(nonce)
+    }
+
+    fn seal(&self, chunk: &[u8], last_chunk: bool) -> Result<ZeroizeBytes, SecureMemError> {
+        let nonce = self.chunk_nonce(last_chunk)?;
+
+        let mut buffer = BytesMut::with_capacity(chunk.len() + Self::TAG_LENGTH);
+        buffer.extend_from_slice(chunk);
+        if self
+            .cipher
+            .encrypt_in_place(&nonce, b"", &mut buffer)
+            .is_err()
+        {
+            wipe_bytes_mut(&mut buffer);
+
+            return Err(SecureMemError::Encryption);
+        }
+
+        Ok(ZeroizeBytes(buffer))
+    }
+
+    fn open(&self, chunk: &[u8], last_chunk: bool) -> Result<ZeroizeBytes, SecureMemError> {
+        if chunk.len() < Self::TAG_LENGTH {
+            return Err(SecureMemError::InvalidLength);
+        }
+        let nonce = self.chunk_nonce(last_chunk)?;
+
+        let mut buffer = BytesMut::with_capacity(chunk.len());
+        buffer.extend_from_slice(chunk);
+        if self
+            .cipher
+            .decrypt_in_place(&nonce, b"", &mut buffer)
+            .is_err()
+        {
+            wipe_bytes_mut(&mut buffer);
+
+            return Err(SecureMemError::AuthenticationFailed);
+        }
+
+        Ok(ZeroizeBytes(buffer))
+    }
+}
+
+#[cfg(all(test, feature = "getrandom"))]
+mod stream_tests {
+    use crate::*;
+
+    #[test]
+    fn exhausted_counter_only_allows_the_last_chunk() {
+        let key = ZeroizeArray::<32>::csprng();
+        let mut stream = EncryptedStream::<XChaCha8Poly1305>::new(key.chacha_key()).unwrap();
+        stream.position = u32::MAX;
+
+        assert_eq!(
+            stream.encrypt_chunk(b"one too many").err(),
+            Some(SecureMemError::CounterExhausted)
+        );
+        assert!(stream.finalize(b"last").is_ok());
+    }
+}
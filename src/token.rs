@@ -0,0 +1,134 @@
+use crate::{store::try_fill_random, SecureMemError, SecureString, ZeroizeArray, ZeroizeBytes};
+#[cfg(feature = "getrandom")]
+use rand_core::OsRng;
+use rand_core::{CryptoRng, RngCore};
+
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const URL_SAFE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const HEX: &[u8] = b"0123456789abcdef";
+
+// `Custom` alphabets must be non-empty, ASCII and free of repeats, a repeated character would be drawn more often
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenAlphabet<'a> {
+    #[default]
+    Alphanumeric,
+    UrlSafe,
+    Hex,
+    Custom(&'a [u8]),
+}
+
+impl TokenAlphabet<'_> {
+    fn characters(&self) -> Result<&[u8], SecureMemError> {
+        let characters = match self {
+            TokenAlphabet::Alphanumeric => ALPHANUMERIC,
+            TokenAlphabet::UrlSafe => URL_SAFE,
+            TokenAlphabet::Hex => HEX,
+            TokenAlphabet::Custom(characters) => characters,
+        };
+
+        let repeated = characters
+            .iter()
+            .enumerate()
+            .any(|(position, character)| characters[..position].contains(character));
+        if characters.is_empty() || !characters.is_ascii() || repeated {
+            return Err(SecureMemError::InvalidAlphabet);
+        }
+
+        Ok(characters)
+    }
+}
+
+// Mints `len` characters for API keys, session tokens or passwords
+#[cfg(feature = "getrandom")]
+pub fn generate_token(len: usize, alphabet: TokenAlphabet) -> Result<SecureString, SecureMemError> {
+    generate_token_from(&mut OsRng, len, alphabet)
+}
+
+// Rejection sampling: a random byte is only used when it falls below the largest multiple of the alphabet size,
+// so every character is equally likely instead of the first `256 % size` ones being favoured
+pub fn generate_token_from<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    len: usize,
+    alphabet: TokenAlphabet,
+) -> Result<SecureString, SecureMemError> {
+    let characters = alphabet.characters()?;
+    let size = characters.len();
+    let limit = 256 - 256 % size;
+
+    let mut token = ZeroizeBytes::with_capacity(len);
+    let mut random = ZeroizeArray::<64>::zeroed();
+    while token.len() < len {
+        random.expose_with_mut(|random| try_fill_random(rng, random))?;
+        random.expose_with(|random| {
+            random
+                .iter()
+                .filter(|byte| usize::from(**byte) < limit)
+                .take(len - token.len())
+                .for_each(|byte| {
+                    token
+                        .0
+                        .extend_from_slice(&[characters[usize::from(*byte) % size]])
+                })
+        });
+    }
+
+    SecureString::from_utf8(token)
+}
+
+#[cfg(test)]
+mod token_tests {
+    use super::*;
+
+    // Counts up through every byte value so the bytes that are rejected are known exactly
+    struct CountingRng(u8);
+
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            unreachable!()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            unreachable!()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.iter_mut().for_each(|byte| {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(1);
+            })
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for CountingRng {}
+
+    #[test]
+    fn biased_bytes_are_rejected() {
+        // 248 is the largest multiple of 62 below 256, so one full cycle yields 248 characters with each drawn four
+        // times and the following draw starts again at byte zero
+        let token =
+            generate_token_from(&mut CountingRng(0), 249, TokenAlphabet::Alphanumeric).unwrap();
+        let token = token.as_str().as_bytes();
+
+        ALPHANUMERIC.iter().for_each(|character| {
+            assert_eq!(
+                token[..248]
+                    .iter()
+                    .filter(|drawn| *drawn == character)
+                    .count(),
+                4
+            )
+        });
+        assert_eq!(token[248], b'A');
+
+        // Only 255 is rejected for three characters
+        let token =
+            generate_token_from(&mut CountingRng(255), 1, TokenAlphabet::Custom(b"xyz")).unwrap();
+        assert_eq!(token.as_str(), "x");
+    }
+}
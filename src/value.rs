@@ -0,0 +1,59 @@
+use crate::{Cipher, EncryptedBytes, SecureMemError, XChaCha8Poly1305};
+use bytemuck::Pod;
+use chacha20poly1305::Key;
+use core::marker::PhantomData;
+use zeroize::Zeroize;
+#[cfg(feature = "getrandom")]
+use {crate::ZeroizeBytes, chacha20poly1305::aead::bytes::BytesMut};
+
+// Seals plain old data by viewing it as bytes, `Pod` rules out padding bytes that could carry uninitialized memory
+pub struct EncryptedValue<T: Pod + Zeroize, C: Cipher = XChaCha8Poly1305> {
+    ciphertext: EncryptedBytes<C>,
+    value: PhantomData<T>,
+}
+
+impl<T: Pod + Zeroize, C: Cipher> EncryptedValue<T, C> {
+    // `value` is wiped before this returns
+    #[cfg(feature = "getrandom")]
+    pub fn seal(mut value: T, key: &Key) -> Result<Self, SecureMemError> {
+        let mut plaintext = ZeroizeBytes::with_capacity(core::mem::size_of::<T>());
+        plaintext.set(BytesMut::from(bytemuck::bytes_of(&value)));
+        value.zeroize();
+
+        let mut ciphertext = EncryptedBytes::new();
+        ciphertext.encrypt(&plaintext, key)?;
+
+        Ok(EncryptedValue {
+            ciphertext,
+            value: PhantomData,
+        })
+    }
+
+    pub fn encrypted_bytes(&self) -> &EncryptedBytes<C> {
+        &self.ciphertext
+    }
+
+    pub fn open(&self, key: &Key) -> Result<T, SecureMemError> {
+        let plaintext = self.ciphertext.decrypt(key)?;
+        if plaintext.len() != core::mem::size_of::<T>() {
+            return Err(SecureMemError::InvalidLength);
+        }
+
+        Ok(bytemuck::pod_read_unaligned(&plaintext.expose()[..]))
+    }
+
+    // The opened value is wiped when `f` returns or unwinds
+    pub fn open_with<R>(&self, key: &Key, f: impl FnOnce(&T) -> R) -> Result<R, SecureMemError> {
+        struct WipeOnDrop<T: Zeroize>(T);
+
+        impl<T: Zeroize> Drop for WipeOnDrop<T> {
+            fn drop(&mut self) {
+                self.0.zeroize()
+            }
+        }
+
+        let value = WipeOnDrop(self.open(key)?);
+
+        Ok(f(&value.0))
+    }
+}
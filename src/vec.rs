@@ -0,0 +1,191 @@
+use alloc::vec::Vec;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// A growable secret buffer that never leaves its old contents behind. `Vec` and `BytesMut` reallocate on growth and
+// free the previous allocation as is, so every path that needs more room here moves the bytes into a fresh
+// allocation itself and wipes the old one before it is freed.
+pub struct ZeroizeVec(Vec<u8>);
+
+impl ZeroizeVec {
+    pub fn new() -> Self {
+        ZeroizeVec(Vec::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ZeroizeVec(Vec::with_capacity(capacity))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    pub fn push(&mut self, byte: u8) -> &mut Self {
+        self.reserve(1);
+        self.0.push(byte);
+
+        self
+    }
+
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) -> &mut Self {
+        self.reserve(bytes.len());
+        self.0.extend_from_slice(bytes);
+
+        self
+    }
+
+    // Grows like `Vec::reserve`, amortized doubling, but through a wiped relocation
+    pub fn reserve(&mut self, additional: usize) -> &mut Self {
+        let required = self
+            .0
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+
+        if required > self.0.capacity() {
+            let capacity = required.max(self.0.capacity() * 2).max(8);
+            drop(self.relocate(capacity));
+        }
+
+        self
+    }
+
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn expose_with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(&self.0)
+    }
+
+    pub fn expose_with_mut<R>(&mut self, f: impl FnOnce(&mut [u8]) -> R) -> R {
+        f(&mut self.0)
+    }
+
+    // Moves the contents into a new allocation and hands back the old, already wiped, one
+    fn relocate(&mut self, capacity: usize) -> Vec<u8> {
+        let mut relocated = Vec::with_capacity(capacity);
+        relocated.extend_from_slice(&self.0);
+
+        let mut previous = core::mem::replace(&mut self.0, relocated);
+        previous.zeroize();
+
+        previous
+    }
+}
+
+impl Default for ZeroizeVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ZeroizeVec {
+    fn clone(&self) -> Self {
+        let mut copy = Vec::with_capacity(self.0.capacity());
+        copy.extend_from_slice(&self.0);
+
+        ZeroizeVec(copy)
+    }
+}
+
+impl From<&[u8]> for ZeroizeVec {
+    fn from(value: &[u8]) -> Self {
+        let mut vec = ZeroizeVec::with_capacity(value.len());
+        vec.0.extend_from_slice(value);
+
+        vec
+    }
+}
+
+impl ConstantTimeEq for ZeroizeVec {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0[..].ct_eq(&other.0[..])
+    }
+}
+
+impl PartialEq for ZeroizeVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for ZeroizeVec {}
+
+// Wipes up to the capacity, not only the initialized length
+impl Zeroize for ZeroizeVec {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for ZeroizeVec {
+    fn drop(&mut self) {
+        self.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for ZeroizeVec {}
+
+impl core::fmt::Debug for ZeroizeVec {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ZeroizeVec([REDACTED; {}])", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod vec_tests {
+    use crate::*;
+    use zeroize::Zeroize;
+
+    fn backing_storage(bytes: &Vec<u8>) -> &[u8] {
+        // The allocation is still alive and every byte up to the capacity has been written by then
+        unsafe { core::slice::from_raw_parts(bytes.as_ptr(), bytes.capacity()) }
+    }
+
+    #[test]
+    fn growth_wipes_the_previous_allocation() {
+        let mut secret = ZeroizeVec::with_capacity(4);
+        secret.extend_from_slice(&[0xC3u8; 4]);
+
+        let previous = secret.relocate(16);
+        assert_eq!(previous.capacity(), 4);
+        assert!(backing_storage(&previous).iter().all(|byte| *byte == 0));
+        assert_eq!(secret.expose(), &[0xC3u8; 4]);
+        assert_eq!(secret.capacity(), 16);
+    }
+
+    #[test]
+    fn pushing_past_capacity_keeps_the_contents() {
+        let mut secret = ZeroizeVec::new();
+        (0..=255u8).for_each(|byte| {
+            secret.push(byte);
+        });
+        secret.extend_from_slice(b"tail");
+
+        assert_eq!(secret.len(), 260);
+        assert!(secret.capacity() >= 260);
+        assert_eq!(secret.expose_with(|bytes| bytes[255]), 255);
+        assert_eq!(&secret.expose()[256..], b"tail");
+
+        let capacity = secret.capacity();
+        secret.reserve(0);
+        assert_eq!(secret.capacity(), capacity);
+
+        let copy = secret.clone();
+        assert!(copy == secret);
+        secret.zeroize();
+        assert!(secret.is_empty());
+        assert!(backing_storage(&secret.0).iter().all(|byte| *byte == 0));
+        assert_eq!(copy.len(), 260);
+        assert_eq!(format!("{copy:?}"), "ZeroizeVec([REDACTED; 260])");
+    }
+}
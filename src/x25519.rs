@@ -0,0 +1,160 @@
+use crate::{store::try_fill_random, SecureMemError, ZeroizeArray};
+use rand_core::{CryptoRng, RngCore};
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroize;
+#[cfg(feature = "getrandom")]
+use {crate::store::OS_RNG_FAILED, rand_core::OsRng};
+
+const SHARED_KEY_INFO: &[u8] = b"xor-securemem 2023-01-01 x25519 sealing key v1";
+
+// One side of an X25519 key agreement (RFC 7748) over `x25519-dalek`. Only the public key leaves the process, the
+// private scalar and every shared secret are wiped when they drop.
+pub struct EphemeralDh {
+    secret: StaticSecret,
+    public: [u8; 32],
+}
+
+impl EphemeralDh {
+    // Panics when the operating system RNG fails, like `ZeroizeArray::csprng`
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Self {
+        Self::try_generate().expect(OS_RNG_FAILED)
+    }
+
+    #[cfg(feature = "getrandom")]
+    pub fn try_generate() -> Result<Self, SecureMemError> {
+        Self::try_generate_from(&mut OsRng)
+    }
+
+    pub fn try_generate_from<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, SecureMemError> {
+        let mut secret = ZeroizeArray::<32>::zeroed();
+        secret.expose_with_mut(|secret| try_fill_random(rng, secret))?;
+
+        Ok(Self::from_secret(secret))
+    }
+
+    fn from_secret(secret: ZeroizeArray<32>) -> Self {
+        let mut bytes = *secret.expose_borrowed();
+        let secret = StaticSecret::from(bytes);
+        bytes.zeroize();
+        let public = PublicKey::from(&secret).to_bytes();
+
+        EphemeralDh { secret, public }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public
+    }
+
+    // Both sides derive the same sealing key, usable anywhere a vault's `sealing_key` is. The raw shared secret is
+    // run through HKDF-SHA256 with both public keys as the salt and wiped, a peer key of low order that forces an all
+    // zero secret is rejected.
+    pub fn agree(&self, their_public: &[u8; 32]) -> Result<ZeroizeArray<32>, SecureMemError> {
+        let shared = self.secret.diffie_hellman(&PublicKey::from(*their_public));
+        if !shared.was_contributory() {
+            return Err(SecureMemError::InvalidPublicKey);
+        }
+        // Copied straight into a wiping array, `shared` wipes itself when it drops
+        let shared = ZeroizeArray::<32>::try_from(&shared.as_bytes()[..])?;
+
+        // The public keys are sorted so the salt does not depend on which side is which
+        let (first, second) = match self.public <= *their_public {
+            true => (&self.public, their_public),
+            false => (their_public, &self.public),
+        };
+        let mut salt = [0u8; 64];
+        salt[..32].copy_from_slice(first);
+        salt[32..].copy_from_slice(second);
+
+        Ok(shared.derive_subkey::<32>(Some(&salt), SHARED_KEY_INFO))
+    }
+}
+
+impl core::fmt::Debug for EphemeralDh {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EphemeralDh")
+            .field("secret", &format_args!("[REDACTED; 32]"))
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod x25519_tests {
+    use super::*;
+
+    fn hex(encoded: &str) -> [u8; 32] {
+        crate::hex(encoded).try_into().unwrap()
+    }
+
+    #[test]
+    fn matches_rfc_7748() {
+        use x25519_dalek::{x25519, X25519_BASEPOINT_BYTES as BASE_POINT};
+
+        assert_eq!(
+            x25519(
+                hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+                hex("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c")
+            ),
+            hex("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+        assert_eq!(
+            x25519(
+                hex("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d"),
+                hex("e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493")
+            ),
+            hex("95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957")
+        );
+
+        // Section 5.2, the ladder fed its own output
+        let (mut scalar, mut u) = (BASE_POINT, BASE_POINT);
+        (1..=1000).for_each(|iteration| {
+            (scalar, u) = (x25519(scalar, u), scalar);
+            if iteration == 1 {
+                assert_eq!(
+                    scalar,
+                    hex("422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079")
+                );
+            }
+        });
+        assert_eq!(
+            scalar,
+            hex("684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51")
+        );
+    }
+
+    #[test]
+    fn rfc_7748_key_agreement() {
+        let alice = EphemeralDh::from_secret(ZeroizeArray::new(hex(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        )));
+        let bob = EphemeralDh::from_secret(ZeroizeArray::new(hex(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        )));
+
+        assert_eq!(
+            alice.public_key(),
+            hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob.public_key(),
+            hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+        let secret = hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(
+            alice
+                .secret
+                .diffie_hellman(&PublicKey::from(bob.public_key()))
+                .as_bytes(),
+            &secret
+        );
+        assert!(alice.agree(&bob.public_key()).unwrap() == bob.agree(&alice.public_key()).unwrap());
+
+        // Zero is a low order point, so the shared secret would be all zeros
+        assert_eq!(
+            alice.agree(&[0u8; 32]).err(),
+            Some(SecureMemError::InvalidPublicKey)
+        );
+        assert!(!format!("{alice:?}").contains(&format!("{:?}", alice.secret.as_bytes())));
+    }
+}